@@ -0,0 +1,39 @@
+//! Compares sequential and parallel line checking on the puzzle input repeated
+//! to about two million lines. Run with `cargo bench --bench parallel`.
+
+use aoc10::{check_lines, check_lines_parallel, GrouperSet};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const TARGET_LINES: usize = 2_000_000;
+const RUNS: usize = 3;
+
+/// Best of `RUNS` timings of `f`
+fn time_best<F: FnMut() -> usize>(mut f: F) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            assert_eq!(f(), TARGET_LINES);
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let input = include_str!("../aoc10.txt");
+    let lines = input.lines()
+        .map(|line| line.as_bytes().to_vec())
+        .cycle()
+        .take(TARGET_LINES)
+        .collect::<Vec<_>>();
+    let groupers = GrouperSet::default();
+    let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+
+    let sequential = time_best(|| check_lines(&groupers, None, 1, &lines).unwrap().len());
+    println!("sequential: {:?} ({:.0} lines/s)", sequential, TARGET_LINES as f64 / sequential.as_secs_f64());
+
+    let parallel = time_best(|| check_lines_parallel(&groupers, None, 1, &lines, threads).unwrap().len());
+    println!("parallel, {} threads: {:?} ({:.0} lines/s)", threads, parallel, TARGET_LINES as f64 / parallel.as_secs_f64());
+    println!("speedup: {:.2}x", sequential.as_secs_f64() / parallel.as_secs_f64());
+}
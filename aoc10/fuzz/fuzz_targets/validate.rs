@@ -0,0 +1,11 @@
+//! Feeds arbitrary bytes through every checker to make sure none of them
+//! panic. Run with `cargo fuzz run validate` from the aoc10 directory.
+
+#![no_main]
+
+use aoc10::run_every_checker;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    run_every_checker(data);
+});
@@ -0,0 +1,164 @@
+use crate::grouper::{Grouper, GrouperSet};
+use crate::lenient::Lenience;
+use crate::repair::{apply_edits, repair_line};
+use crate::stats::LineStats;
+use crate::tree::parse_line;
+use crate::validator::{eval_line, BracketValidator, LineResult};
+
+/// Small deterministic PRNG (SplitMix64), so generated lines are reproducible
+/// from a seed without pulling in a random number crate
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..bound`, which must be nonzero
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+/// A generated line together with the result checking it must give
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedLine {
+    pub text: String,
+    pub expected: LineResult,
+}
+
+/// Builds random lines of known validity from a grouper table
+pub struct LineGenerator<'a> {
+    groupers: &'a [Grouper],
+    rng: SplitMix64,
+    /// Deepest nesting of the valid stretches between unclosed groupers, which
+    /// also bounds the unclosed groupers and the tail of corrupted lines. Values
+    /// below 1 are treated as 1 for those bounds.
+    pub max_depth: usize,
+    /// Most groupers in a row at one nesting level of a valid stretch
+    pub max_width: usize,
+}
+
+impl<'a> LineGenerator<'a> {
+    pub fn new(groupers: &'a GrouperSet, seed: u64) -> Self {
+        assert!(!groupers.groupers().is_empty(), "can't generate lines without groupers");
+        LineGenerator { groupers: groupers.groupers(), rng: SplitMix64::new(seed), max_depth: 5, max_width: 2 }
+    }
+
+    fn grouper(&mut self) -> &'a Grouper {
+        &self.groupers[self.rng.below(self.groupers.len())]
+    }
+
+    /// Appends a balanced run of groupers, possibly empty
+    fn push_valid(&mut self, text: &mut String, depth: usize) {
+        if depth >= self.max_depth {
+            return;
+        }
+
+        for _ in 0..self.rng.below(self.max_width + 1) {
+            let grouper = self.grouper();
+            text.push(grouper.open);
+            self.push_valid(text, depth + 1);
+            text.push(grouper.close);
+        }
+    }
+
+    /// Appends `unclosed` openers with valid stretches around them, returning
+    /// the openers' groupers outermost first
+    fn push_open(&mut self, text: &mut String, unclosed: usize) -> Vec<&'a Grouper> {
+        let mut stack = Vec::new();
+        self.push_valid(text, 0);
+        for _ in 0..unclosed {
+            let grouper = self.grouper();
+            text.push(grouper.open);
+            stack.push(grouper);
+            self.push_valid(text, 0);
+        }
+        stack
+    }
+
+    pub fn valid(&mut self) -> GeneratedLine {
+        let mut text = String::new();
+        self.push_valid(&mut text, 0);
+        GeneratedLine { text, expected: LineResult::Valid }
+    }
+
+    pub fn incomplete(&mut self) -> GeneratedLine {
+        let mut text = String::new();
+        let unclosed = 1 + self.rng.below(self.max_depth.max(1));
+        let stack = self.push_open(&mut text, unclosed);
+        let missing_closers = stack.iter().rev().map(|grouper| grouper.close).collect();
+        GeneratedLine { text, expected: LineResult::Incomplete { missing_closers } }
+    }
+
+    /// Line whose first corruption is a wrong closer after a valid prefix,
+    /// followed by arbitrary delimiters. Needs at least two groupers when the
+    /// prefix leaves a grouper open, so there is a wrong closer to pick.
+    pub fn corrupted(&mut self) -> GeneratedLine {
+        let mut text = String::new();
+        let unclosed = if self.groupers.len() > 1 { self.rng.below(self.max_depth + 1) } else { 0 };
+        let stack = self.push_open(&mut text, unclosed);
+        let expected = stack.last().map(|grouper| grouper.close);
+
+        let found = loop {
+            let close = self.grouper().close;
+            if Some(close) != expected {
+                break close;
+            }
+        };
+        let column = text.chars().count() + 1;
+        text.push(found);
+
+        for _ in 0..self.rng.below(self.max_depth.max(1) * 2) {
+            let grouper = self.grouper();
+            text.push(if self.rng.below(2) == 0 { grouper.open } else { grouper.close });
+        }
+
+        GeneratedLine { text, expected: LineResult::Corrupted { column, expected, found } }
+    }
+
+    /// One of the three kinds of line, picked at random
+    pub fn any(&mut self) -> GeneratedLine {
+        match self.rng.below(3) {
+            0 => self.valid(),
+            1 => self.incomplete(),
+            _ => self.corrupted(),
+        }
+    }
+}
+
+/// Feeds arbitrary bytes through every checker, strict and lenient, ignoring
+/// what they return. The `validate` fuzz target and the generated tests both
+/// call this to make sure none of them panic.
+pub fn run_every_checker(data: &[u8]) {
+    let groupers = GrouperSet::default();
+    let lenience = Lenience { skip_regions: vec!["\" \" \\".parse().unwrap(), "// $".parse().unwrap()] };
+
+    let mut validator = BracketValidator::new(&groupers);
+    if validator.push_bytes(data).is_ok() {
+        let _ = validator.finish();
+    }
+
+    let text = String::from_utf8_lossy(data);
+    for line in text.split('\n') {
+        for lenience in [None, Some(&lenience)] {
+            let _ = eval_line(&groupers, lenience, line);
+            let _ = parse_line(&groupers, lenience, line);
+            let _ = LineStats::of(&groupers, lenience, line);
+            if let Ok(Some(edits)) = repair_line(&groupers, lenience, line) {
+                apply_edits(line, &edits);
+            }
+        }
+    }
+}
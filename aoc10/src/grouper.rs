@@ -0,0 +1,111 @@
+use anyhow::{anyhow, bail, Context, Error, Result};
+use std::collections::HashSet;
+use std::io::BufRead;
+use std::str::FromStr;
+
+/// Pair of delimiters with the scores the puzzle assigns to them
+#[derive(Debug, Clone)]
+pub struct Grouper {
+    pub open: char,
+    pub close: char,
+    /// Points per missing closer when completing a line
+    pub inc_score: u64,
+    /// Points for a corrupted line whose first bad closer is this one
+    pub bad_score: u64,
+}
+
+impl FromStr for Grouper {
+    type Err = Error;
+    /// Parses `open close inc_score bad_score`, e.g. `( ) 1 3`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = s.split_whitespace().collect::<Vec<_>>();
+        if fields.len() != 4 {
+            bail!("expected \"open close inc_score bad_score\", found {:?}", s);
+        }
+
+        let single_char = |field: &str| {
+            let mut chars = field.chars();
+            match (chars.next(), chars.next()) {
+                (Some(ch), None) => Ok(ch),
+                _ => Err(anyhow!("delimiter {:?} is not a single character", field)),
+            }
+        };
+
+        Ok(Grouper {
+            open: single_char(fields[0])?,
+            close: single_char(fields[1])?,
+            inc_score: fields[2].parse()?,
+            bad_score: fields[3].parse()?,
+        })
+    }
+}
+
+/// The four groupers from the puzzle, used when no other table is given
+pub const GROUPERS: [Grouper; 4] = [
+    Grouper { open: '(', close: ')', inc_score: 1u64, bad_score: 3u64 },
+    Grouper { open: '[', close: ']', inc_score: 2u64, bad_score: 57u64 },
+    Grouper { open: '{', close: '}', inc_score: 3u64, bad_score: 1197u64 },
+    Grouper { open: '<', close: '>', inc_score: 4u64, bad_score: 25137u64 },
+];
+
+/// Table of groupers a line is checked against
+#[derive(Debug, Clone)]
+pub struct GrouperSet {
+    groupers: Vec<Grouper>,
+}
+
+impl GrouperSet {
+    /// Checks that every delimiter character belongs to exactly one grouper
+    pub fn new(groupers: Vec<Grouper>) -> Result<Self> {
+        let mut seen = HashSet::new();
+        for grouper in &groupers {
+            if grouper.open == grouper.close {
+                bail!("grouper {:?} opens and closes with the same character", grouper.open);
+            }
+            for ch in [grouper.open, grouper.close] {
+                if !seen.insert(ch) {
+                    bail!("character {:?} is used by more than one grouper", ch);
+                }
+            }
+        }
+
+        Ok(GrouperSet { groupers })
+    }
+
+    /// Reads one grouper per line, skipping blank lines and `#` comments
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self> {
+        let mut groupers = Vec::new();
+        for (idx, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            groupers.push(line.parse().with_context(|| format!("grouper config line {}", idx + 1))?);
+        }
+
+        Self::new(groupers)
+    }
+
+    pub fn groupers(&self) -> &[Grouper] {
+        &self.groupers
+    }
+
+    pub fn into_groupers(self) -> Vec<Grouper> {
+        self.groupers
+    }
+
+    pub fn by_open(&self, open: char) -> Option<&Grouper> {
+        self.groupers.iter().find(|grouper| grouper.open == open)
+    }
+
+    pub fn by_close(&self, close: char) -> Option<&Grouper> {
+        self.groupers.iter().find(|grouper| grouper.close == close)
+    }
+}
+
+impl Default for GrouperSet {
+    fn default() -> Self {
+        GrouperSet { groupers: GROUPERS.to_vec() }
+    }
+}
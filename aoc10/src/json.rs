@@ -0,0 +1,64 @@
+use serde_json::{json, Map, Value};
+use crate::grouper::GrouperSet;
+use crate::score::{Aggregation, CompletionScore, Scores, PERCENTILES};
+use crate::validator::{CheckedLine, LineResult};
+
+/// Completion scores too large for a JSON number are written as strings
+fn score_json(score: &CompletionScore) -> Value {
+    match score {
+        CompletionScore::Small(score) => Value::from(*score),
+        CompletionScore::Big(score) => Value::from(score.to_string()),
+    }
+}
+
+/// Report object for one checked line
+pub fn line_json(groupers: &GrouperSet, line: &CheckedLine) -> Value {
+    let (status, column, expected, found, score) = match &line.result {
+        LineResult::Valid => ("valid", None, None, None, Value::from(0)),
+        LineResult::Corrupted { column, expected, found } => {
+            ("corrupted", Some(*column), *expected, Some(*found), Value::from(line.result.bad_score(groupers)))
+        }
+        LineResult::Incomplete { .. } => {
+            ("incomplete", Some(line.end_column), None, None, score_json(&line.result.completion_score(groupers)))
+        }
+    };
+
+    json!({
+        "type": "line",
+        "line": line.number,
+        "status": status,
+        "column": column,
+        "expected": expected,
+        "found": found,
+        "completion": line.result.completion(),
+        "score": score,
+    })
+}
+
+/// Report object with the totals for both parts. Part 2 is combined using
+/// `aggregation`, and is null if that fails, e.g. when there are no scores.
+pub fn summary_json(scores: &Scores, aggregation: Aggregation) -> Value {
+    let completion_score = match aggregation {
+        Aggregation::Median(median) => scores.median_completion_score(median).ok().map(|score| score_json(&score)),
+        Aggregation::Mean => scores.mean_completion_score().ok().map(Value::from),
+        Aggregation::Percentiles => {
+            let mut percentiles = Map::new();
+            for percent in PERCENTILES {
+                if let Ok(score) = scores.completion_percentile(percent) {
+                    percentiles.insert(format!("p{}", percent), score_json(&score));
+                }
+            }
+            Some(Value::Object(percentiles))
+        }
+        Aggregation::Histogram => Some(Value::from(scores.completion_histogram()
+            .into_iter()
+            .map(|(score, count)| json!({ "score": score_json(&score), "count": count }))
+            .collect::<Vec<_>>())),
+    };
+
+    json!({
+        "type": "summary",
+        "syntax_error_score": scores.syntax_error_score(),
+        "completion_score": completion_score,
+    })
+}
@@ -0,0 +1,187 @@
+use anyhow::{bail, Error, Result};
+use std::str::FromStr;
+use crate::grouper::GrouperSet;
+
+/// Span of a line whose contents are ignored entirely, such as a string literal
+/// or a comment. Regions never continue past the end of a line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkipRegion {
+    pub start: String,
+    /// `None` means the region runs to the end of the line
+    pub end: Option<String>,
+    /// Character that makes the following character part of the region, e.g. `\` in strings
+    pub escape: Option<char>,
+}
+
+impl FromStr for SkipRegion {
+    type Err = Error;
+    /// Parses `start end [escape]`, where an end of `$` means end of line, e.g. `" " \` or `// $`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = s.split_whitespace().collect::<Vec<_>>();
+        if fields.len() != 2 && fields.len() != 3 {
+            bail!("expected \"start end [escape]\", found {:?}", s);
+        }
+
+        let end = match fields[1] {
+            "$" => None,
+            end => Some(end.to_string()),
+        };
+
+        let escape = match fields.get(2) {
+            Some(field) => {
+                let mut chars = field.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(ch), None) => Some(ch),
+                    _ => bail!("escape {:?} is not a single character", field),
+                }
+            }
+            None => None,
+        };
+
+        Ok(SkipRegion { start: fields[0].to_string(), end, escape })
+    }
+}
+
+/// Settings for lenient checking, where characters that are not delimiters are
+/// skipped instead of being an error
+#[derive(Debug, Clone, Default)]
+pub struct Lenience {
+    pub skip_regions: Vec<SkipRegion>,
+}
+
+enum FilterState {
+    Code,
+    InRegion {
+        region: usize,
+        /// Last few characters seen, to spot the end of the region
+        recent: Vec<char>,
+        escaped: bool,
+    },
+}
+
+/// Streaming filter that passes on only the delimiters of a line that lie
+/// outside of skip regions, along with their columns
+pub struct DelimiterFilter<'a> {
+    groupers: &'a GrouperSet,
+    lenience: &'a Lenience,
+    state: FilterState,
+    /// Characters that might be the start of a skip region
+    pending: Vec<(usize, char)>,
+    /// Column of the last non-whitespace character of code on this line, or 0.
+    /// A closed skip region counts as code, ending at its last character.
+    last_code: usize,
+}
+
+impl<'a> DelimiterFilter<'a> {
+    pub fn new(groupers: &'a GrouperSet, lenience: &'a Lenience) -> Self {
+        DelimiterFilter { groupers, lenience, state: FilterState::Code, pending: Vec::new(), last_code: 0 }
+    }
+
+    /// Feeds the character at `column`, adding any delimiters it releases to `out`
+    pub fn push(&mut self, column: usize, ch: char, out: &mut Vec<(usize, char)>) {
+        match &mut self.state {
+            FilterState::Code => {
+                self.pending.push((column, ch));
+                self.scan_pending(out);
+            }
+            FilterState::InRegion { region, recent, escaped } => {
+                let region = &self.lenience.skip_regions[*region];
+                if *escaped {
+                    *escaped = false;
+                } else if region.escape == Some(ch) {
+                    *escaped = true;
+                } else if let Some(end) = &region.end {
+                    recent.push(ch);
+                    let end_len = end.chars().count();
+                    if recent.len() > end_len {
+                        recent.remove(0);
+                    }
+                    if recent.iter().copied().eq(end.chars()) {
+                        self.state = FilterState::Code;
+                        self.last_code = column;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Releases anything still pending and resets for the next line. Returns
+    /// the column just past the line's code, where closers that complete it go:
+    /// after the last non-whitespace character outside any skip region, or
+    /// after a region that was closed, but before one running to the end of
+    /// the line or left open.
+    pub fn end_line(&mut self, out: &mut Vec<(usize, char)>) -> usize {
+        for (column, ch) in std::mem::take(&mut self.pending) {
+            self.release(column, ch, out);
+        }
+        self.state = FilterState::Code;
+        std::mem::take(&mut self.last_code) + 1
+    }
+
+    fn release(&mut self, column: usize, ch: char, out: &mut Vec<(usize, char)>) {
+        if !ch.is_whitespace() {
+            self.last_code = column;
+        }
+        if self.groupers.by_open(ch).is_some() || self.groupers.by_close(ch).is_some() {
+            out.push((column, ch));
+        }
+    }
+
+    fn scan_pending(&mut self, out: &mut Vec<(usize, char)>) {
+        while !self.pending.is_empty() {
+            let text = self.pending.iter().map(|&(_, ch)| ch).collect::<String>();
+            if let Some(region) = self.lenience.skip_regions.iter().position(|region| region.start == text) {
+                self.pending.clear();
+                self.state = FilterState::InRegion { region, recent: Vec::new(), escaped: false };
+                return;
+            }
+            if self.lenience.skip_regions.iter().any(|region| region.start.starts_with(&text)) {
+                return;
+            }
+
+            // The first pending character can't start a region, so it is plain code
+            let (column, ch) = self.pending.remove(0);
+            self.release(column, ch, out);
+        }
+    }
+}
+
+/// Delimiters of a whole line with their 1-based columns
+pub fn line_delimiters(groupers: &GrouperSet, lenience: &Lenience, line: &str) -> Vec<(usize, char)> {
+    let mut filter = DelimiterFilter::new(groupers, lenience);
+    let mut delimiters = Vec::new();
+    for (idx, ch) in line.chars().enumerate() {
+        filter.push(idx + 1, ch, &mut delimiters);
+    }
+    filter.end_line(&mut delimiters);
+    delimiters
+}
+
+/// Column just past the code of `line`, where closers that complete it go, as
+/// returned by `DelimiterFilter::end_line`. That is ahead of a trailing comment.
+pub fn code_end(groupers: &GrouperSet, lenience: Option<&Lenience>, line: &str) -> usize {
+    let lenience = match lenience {
+        Some(lenience) => lenience,
+        None => return line.chars().count() + 1,
+    };
+    let mut filter = DelimiterFilter::new(groupers, lenience);
+    let mut delimiters = Vec::new();
+    for (idx, ch) in line.chars().enumerate() {
+        filter.push(idx + 1, ch, &mut delimiters);
+    }
+    filter.end_line(&mut delimiters)
+}
+
+/// Delimiters of a whole line with their 1-based columns. Without a `lenience`
+/// every character must be a delimiter.
+pub fn delimiters(groupers: &GrouperSet, lenience: Option<&Lenience>, line: &str) -> Result<Vec<(usize, char)>> {
+    match lenience {
+        Some(lenience) => Ok(line_delimiters(groupers, lenience, line)),
+        None => {
+            if let Some(ch) = line.chars().find(|&ch| groupers.by_open(ch).is_none() && groupers.by_close(ch).is_none()) {
+                bail!("unknown character {:?}", ch);
+            }
+            Ok(line.chars().enumerate().map(|(idx, ch)| (idx + 1, ch)).collect())
+        }
+    }
+}
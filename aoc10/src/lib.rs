@@ -0,0 +1,20 @@
+pub mod generate;
+pub mod grouper;
+pub mod json;
+pub mod lenient;
+pub mod parallel;
+pub mod repair;
+pub mod score;
+pub mod stats;
+pub mod tree;
+pub mod validator;
+
+pub use generate::{run_every_checker, GeneratedLine, LineGenerator, SplitMix64};
+pub use grouper::{Grouper, GrouperSet, GROUPERS};
+pub use lenient::{code_end, delimiters, line_delimiters, DelimiterFilter, Lenience, SkipRegion};
+pub use parallel::{check_lines, check_lines_parallel};
+pub use repair::{apply_edits, edit_diagnostics, repair_line, Edit, MAX_REPAIR_LEN};
+pub use score::{Aggregation, CompletionScore, Median, Scores, PERCENTILES};
+pub use stats::{CorpusStats, LineStats};
+pub use tree::{parse_line, GroupNode, ParseTree};
+pub use validator::{eval_line, validate_reader, BracketValidator, CheckedLine, LineResult};
@@ -1,70 +1,327 @@
-use anyhow::{bail, Result};
-use std::io::{self, BufRead};
+use anyhow::{anyhow, bail, Context, Error, Result};
+use aoc10::{
+    apply_edits, check_lines_parallel, edit_diagnostics, parse_line, repair_line, Aggregation, BracketValidator,
+    CheckedLine, CorpusStats, Edit, GrouperSet, Lenience, LineResult, LineStats, Scores, PERCENTILES,
+};
+use aoc10::json::{line_json, summary_json};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::str::FromStr;
+use std::thread;
 
-const PART2: bool = true;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+    One,
+    Two,
+    Both,
+}
+
+impl FromStr for Part {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" => Ok(Part::One),
+            "2" => Ok(Part::Two),
+            "both" => Ok(Part::Both),
+            _ => bail!("unknown part {:?}, expected 1, 2 or both", s),
+        }
+    }
+}
 
-struct Grouper {
-    open: u8,
-    close: u8,
-    inc_score: u64,
-    bad_score: u64,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    /// One JSON object per line, then a summary object
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => bail!("unknown format {:?}, expected text or json", s),
+        }
+    }
 }
 
-const GROUPERS: [Grouper; 4] = [
-    Grouper { open: b'(', close: b')', inc_score: 1u64, bad_score: 3u64 },
-    Grouper { open: b'[', close: b']', inc_score: 2u64, bad_score: 57u64 },
-    Grouper { open: b'{', close: b'}', inc_score: 3u64, bad_score: 1197u64 },
-    Grouper { open: b'<', close: b'>', inc_score: 4u64, bad_score: 25137u64 },
-];
-
-fn eval_line(line: io::Result<Vec<u8>>) -> Result<u64> {
-    let mut grouper_stack = Vec::new();
-    let mut bad_score = 0;
-    for ch in line?.into_iter() {
-        if let Some(grouper) = GROUPERS.iter().find(|grouper| grouper.open == ch) {
-            grouper_stack.push(grouper);
-        } else if let Some(grouper) = GROUPERS.iter().find(|grouper| grouper.close == ch) {
-            if grouper_stack.last().map(|grouper| grouper.open) == Some(grouper.open) {
-                grouper_stack.pop();
+struct Options {
+    part: Part,
+    /// How part 2 combines the completion scores
+    aggregation: Aggregation,
+    format: Format,
+    /// Use arbitrary precision for completion scores that overflow a u64
+    big_scores: bool,
+    diagnostics: bool,
+    /// Print the completion string and score of each incomplete line
+    complete: bool,
+    /// Write valid and completed lines to stdout instead of the score report.
+    /// With --recover, corrupted lines are repaired and written as well.
+    repair: bool,
+    /// Report every corruption in a line using a minimal repair
+    recover: bool,
+    /// Print the nesting tree of each line
+    tree: bool,
+    /// Print nesting and grouper statistics per line and for the whole input
+    stats: bool,
+    /// Worker threads for checking lines, where 0 means one per core
+    threads: usize,
+    /// Skip characters that aren't delimiters, and these regions, instead of failing
+    lenience: Option<Lenience>,
+    /// Grouper table to load instead of the default four
+    groupers_file: Option<String>,
+    /// Groupers to add to the table from the command line, in `Grouper::from_str` format
+    grouper_specs: Vec<String>,
+}
+
+impl Options {
+    fn from_args<I: Iterator<Item = String>>(mut args: I) -> Result<Self> {
+        let mut part = Part::Two;
+        let mut aggregation = Aggregation::default();
+        let mut format = Format::Text;
+        let mut big_scores = false;
+        let mut diagnostics = false;
+        let mut complete = false;
+        let mut repair = false;
+        let mut recover = false;
+        let mut tree = false;
+        let mut stats = false;
+        let mut threads = 1;
+        let mut lenience: Option<Lenience> = None;
+        let mut groupers_file = None;
+        let mut grouper_specs = Vec::new();
+
+        while let Some(arg) = args.next() {
+            if arg == "--part" {
+                part = args.next().ok_or_else(|| anyhow!("--part requires a value"))?.parse()?;
+            } else if let Some(value) = arg.strip_prefix("--part=") {
+                part = value.parse()?;
+            } else if arg == "--aggregate" {
+                aggregation = args.next().ok_or_else(|| anyhow!("--aggregate requires a value"))?.parse()?;
+            } else if arg == "--format" {
+                format = args.next().ok_or_else(|| anyhow!("--format requires a value"))?.parse()?;
+            } else if let Some(value) = arg.strip_prefix("--format=") {
+                format = value.parse()?;
+            } else if arg == "--big-scores" {
+                big_scores = true;
+            } else if arg == "--diagnostics" {
+                diagnostics = true;
+            } else if arg == "--complete" {
+                complete = true;
+            } else if arg == "--repair" {
+                repair = true;
+            } else if arg == "--recover" {
+                recover = true;
+            } else if arg == "--tree" {
+                tree = true;
+            } else if arg == "--stats" {
+                stats = true;
+            } else if arg == "--threads" {
+                threads = args.next().ok_or_else(|| anyhow!("--threads requires a count"))?.parse()?;
+            } else if arg == "--lenient" {
+                lenience.get_or_insert_with(Lenience::default);
+            } else if arg == "--skip" {
+                let spec = args.next().ok_or_else(|| anyhow!("--skip requires a spec"))?;
+                lenience.get_or_insert_with(Lenience::default).skip_regions.push(spec.parse()?);
+            } else if arg == "--groupers" {
+                groupers_file = Some(args.next().ok_or_else(|| anyhow!("--groupers requires a path"))?);
+            } else if arg == "--grouper" {
+                grouper_specs.push(args.next().ok_or_else(|| anyhow!("--grouper requires a spec"))?);
             } else {
-                bad_score = grouper.bad_score;
-                break;
+                bail!("unknown argument {:?}", arg);
             }
-        } else {
-            bail!("unknown character {}", ch);
         }
-    };
 
-    let line_score = if PART2 {
-        if bad_score == 0 {
-            // Process partial/complete line
-            grouper_stack.iter().fold(0, |accum, grouper| accum * 5 + grouper.inc_score)
-        } else {
-            // Discard broken line
-            0
+        if complete && repair {
+            bail!("--complete and --repair both write to stdout, pick one");
+        }
+        if threads != 1 && (repair || recover || tree || stats) {
+            bail!("--threads can't be combined with --repair, --recover, --tree or --stats");
+        }
+        if threads == 0 {
+            threads = thread::available_parallelism()?.get();
+        }
+        if repair && (tree || stats) {
+            bail!("--repair writes lines to stdout, so it can't be combined with --tree or --stats");
+        }
+        if format == Format::Json && (complete || repair || tree || stats) {
+            bail!("--format json can't be combined with --complete, --repair, --tree or --stats");
+        }
+
+        Ok(Options { part, aggregation, format, big_scores, diagnostics, complete, repair, recover, tree, stats, threads, lenience, groupers_file, grouper_specs })
+    }
+
+    /// Builds the grouper table from --groupers, or the default four without it,
+    /// and adds any --grouper specs to it
+    fn groupers(&self) -> Result<GrouperSet> {
+        let mut groupers = match &self.groupers_file {
+            Some(path) => {
+                let file = File::open(path).with_context(|| format!("opening {}", path))?;
+                GrouperSet::from_reader(BufReader::new(file))?.into_groupers()
+            }
+            None => GrouperSet::default().into_groupers(),
+        };
+        for spec in &self.grouper_specs {
+            groupers.push(spec.parse()?);
         }
-    } else {
-        bad_score
-    };
 
-    Ok(line_score)
+        GrouperSet::new(groupers)
+    }
+}
+
+/// Lines each worker thread checks per batch when running with --threads
+const BATCH_LINES_PER_THREAD: usize = 16 * 1024;
+
+/// Prints the part 2 answer, each line starting with `prefix`
+fn print_completion_score(scores: &Scores, aggregation: Aggregation, prefix: &str) -> Result<()> {
+    match aggregation {
+        Aggregation::Median(median) => println!("{}score is {}", prefix, scores.median_completion_score(median)?),
+        Aggregation::Mean => println!("{}mean score is {}", prefix, scores.mean_completion_score()?),
+        Aggregation::Percentiles => {
+            for percent in PERCENTILES {
+                println!("{}p{} score is {}", prefix, percent, scores.completion_percentile(percent)?);
+            }
+        }
+        Aggregation::Histogram => {
+            for (score, count) in scores.completion_histogram() {
+                println!("{}score {} seen {} times", prefix, score, count);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reports a checked line. With `edits` from `--recover`, diagnostics cover the
+/// whole repair script rather than just the first corruption.
+fn report(options: &Options, groupers: &GrouperSet, line: &CheckedLine, edits: Option<&[Edit]>) {
+    if options.diagnostics {
+        if let Some(edits) = edits {
+            for message in edit_diagnostics(edits, line.number, line.end_column) {
+                eprintln!("{}", message);
+            }
+        } else if let Some(message) = line.result.diagnostic(line.number, line.end_column) {
+            eprintln!("{}", message);
+        }
+    }
+    if options.format == Format::Json {
+        println!("{}", line_json(groupers, line));
+    }
+    if options.complete {
+        if let Some(completion) = line.result.completion() {
+            println!("line {}: {} scores {}", line.number, completion, line.result.completion_score(groupers));
+        }
+    }
 }
 
 fn main() -> Result<()> {
-    let mut scores = io::stdin().lock().split(b'\n').map(eval_line).collect::<Result<Vec<u64>>>()?;
-    scores.retain(|&score| score != 0);
+    let options = Options::from_args(env::args().skip(1))?;
+    let groupers = options.groupers()?;
+    let mut scores = if options.big_scores { Scores::with_big_scores() } else { Scores::new() };
+
+    let mut corpus_stats = CorpusStats::new(&groupers);
+
+    if options.repair || options.recover || options.tree || options.stats {
+        // These modes need the text of each line, so read whole lines
+        let mut validator = match &options.lenience {
+            Some(lenience) => BracketValidator::lenient(&groupers, lenience),
+            None => BracketValidator::new(&groupers),
+        };
+        for (idx, line) in io::stdin().lock().split(b'\n').enumerate() {
+            let mut line = String::from_utf8(line?).with_context(|| format!("line {} is not valid UTF-8", idx + 1))?;
+            // Keep a CRLF ending out of the way so completions go before it
+            let line_ending = if options.lenience.is_some() && line.ends_with('\r') {
+                line.pop();
+                "\r\n"
+            } else {
+                "\n"
+            };
+            for ch in line.chars() {
+                validator.push_char(ch)?;
+            }
+            let checked = validator.end_line();
 
-    let final_score = if PART2 {
-        if scores.len() % 2 != 1 {
-            bail!("not an odd amount of scores");
+            let edits = if options.recover && checked.result != LineResult::Valid {
+                // Lines too long to repair fall back to reporting the first corruption
+                repair_line(&groupers, options.lenience.as_ref(), &line).with_context(|| format!("repairing line {}", checked.number))?
+            } else if options.recover {
+                Some(Vec::new())
+            } else {
+                None
+            };
+            report(&options, &groupers, &checked, edits.as_deref());
+
+            if options.tree {
+                let tree = parse_line(&groupers, options.lenience.as_ref(), &line)?;
+                println!("line {}: depth {}", checked.number, tree.max_depth());
+                print!("{}", tree);
+            }
+            if options.stats {
+                let line_stats = LineStats::of(&groupers, options.lenience.as_ref(), &line)?;
+                println!("line {}: {}", checked.number, line_stats.describe(&groupers));
+                corpus_stats.add(&line_stats);
+            }
+
+            if options.repair {
+                match &edits {
+                    Some(edits) => print!("{}{}", apply_edits(&line, edits), line_ending),
+                    None if !matches!(checked.result, LineResult::Corrupted { .. }) => {
+                        // The completion goes before any comment that ends the line
+                        let at = line.char_indices().nth(checked.end_column - 1).map_or(line.len(), |(idx, _)| idx);
+                        let mut stdout = io::stdout().lock();
+                        stdout.write_all(&line.as_bytes()[..at])?;
+                        stdout.write_all(checked.result.completion().unwrap_or_default().as_bytes())?;
+                        stdout.write_all(&line.as_bytes()[at..])?;
+                        stdout.write_all(line_ending.as_bytes())?;
+                    }
+                    None => (),
+                }
+            }
+            scores.add(&groupers, &checked)?;
         }
+    } else if options.threads > 1 {
+        // Check batches of lines on worker threads, then report them in order
+        let mut lines = io::stdin().lock().split(b'\n');
+        let mut first_line = 1;
+        loop {
+            let batch = lines.by_ref().take(options.threads * BATCH_LINES_PER_THREAD).collect::<io::Result<Vec<_>>>()?;
+            if batch.is_empty() {
+                break;
+            }
 
-        scores.sort();
-        scores[scores.len() / 2]
+            for checked in check_lines_parallel(&groupers, options.lenience.as_ref(), first_line, &batch, options.threads)? {
+                report(&options, &groupers, &checked, None);
+                scores.add(&groupers, &checked)?;
+            }
+            first_line += batch.len();
+        }
     } else {
-        scores.iter().fold(0, |accum, item| accum + item)
-    };
+        scores.add_reader(&groupers, options.lenience.as_ref(), io::stdin().lock(), |checked| {
+            report(&options, &groupers, checked, None);
+        })?;
+    }
+
+    if options.repair {
+        return Ok(());
+    }
+    if options.stats {
+        print!("{}", corpus_stats);
+    }
+    if options.format == Format::Json {
+        println!("{}", summary_json(&scores, options.aggregation));
+        return Ok(());
+    }
+
+    match options.part {
+        Part::One => println!("final score is {}", scores.syntax_error_score()),
+        Part::Two => print_completion_score(&scores, options.aggregation, "final ")?,
+        Part::Both => {
+            println!("part 1 syntax error score is {}", scores.syntax_error_score());
+            print_completion_score(&scores, options.aggregation, "part 2 completion ")?;
+        }
+    }
 
-    println!("final score is {}", final_score);
     Ok(())
 }
@@ -0,0 +1,54 @@
+use anyhow::{anyhow, Result};
+use std::thread;
+use crate::grouper::GrouperSet;
+use crate::lenient::Lenience;
+use crate::validator::{BracketValidator, CheckedLine};
+
+/// Checks already split lines (without their newlines) in order, numbering them
+/// from `first_line`
+pub fn check_lines(
+    groupers: &GrouperSet,
+    lenience: Option<&Lenience>,
+    first_line: usize,
+    lines: &[Vec<u8>],
+) -> Result<Vec<CheckedLine>> {
+    let mut validator = match lenience {
+        Some(lenience) => BracketValidator::lenient(groupers, lenience),
+        None => BracketValidator::new(groupers),
+    }.with_first_line(first_line);
+
+    lines.iter()
+        .map(|line| {
+            for &b in line {
+                validator.push_byte(b)?;
+            }
+            validator.finish_line()
+        })
+        .collect()
+}
+
+/// Same as `check_lines`, but splits the lines into one contiguous slice per
+/// thread. Results come back in input order, and an error is the one for the
+/// earliest failing line.
+pub fn check_lines_parallel(
+    groupers: &GrouperSet,
+    lenience: Option<&Lenience>,
+    first_line: usize,
+    lines: &[Vec<u8>],
+    threads: usize,
+) -> Result<Vec<CheckedLine>> {
+    let chunk_len = lines.len().div_ceil(threads.max(1)).max(1);
+
+    thread::scope(|scope| {
+        let workers = lines.chunks(chunk_len)
+            .enumerate()
+            .map(|(idx, chunk)| scope.spawn(move || check_lines(groupers, lenience, first_line + idx * chunk_len, chunk)))
+            .collect::<Vec<_>>();
+
+        let mut checked = Vec::with_capacity(lines.len());
+        for worker in workers {
+            checked.extend(worker.join().map_err(|_| anyhow!("line checking thread panicked"))??);
+        }
+        Ok(checked)
+    })
+}
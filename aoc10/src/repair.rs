@@ -0,0 +1,180 @@
+use anyhow::Result;
+use crate::grouper::GrouperSet;
+use crate::lenient::{code_end, delimiters, Lenience};
+
+/// Most delimiters `repair_line` accepts in a line, since its cost is cubic in their number
+pub const MAX_REPAIR_LEN: usize = 1024;
+
+/// Single edit in a repair script. Columns refer to the original line and are
+/// 1-based; an insertion at column `len + 1` appends to the end of the line.
+/// Closers that complete the line are inserted at its end column, which in
+/// lenient mode is just past the last character outside a skip region.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    Insert { column: usize, ch: char },
+    Delete { column: usize, found: char },
+    Replace { column: usize, found: char, replacement: char },
+}
+
+impl Edit {
+    pub fn column(&self) -> usize {
+        match self {
+            Edit::Insert { column, .. } | Edit::Delete { column, .. } | Edit::Replace { column, .. } => *column,
+        }
+    }
+
+    /// Compiler-style message for this edit, given the end column of its line
+    pub fn diagnostic(&self, line_num: usize, end_column: usize) -> String {
+        match self {
+            Edit::Insert { column, ch } if *column >= end_column => format!(
+                "line {}, col {}: unexpected end of line, expected '{}'", line_num, column, ch),
+            Edit::Insert { column, ch } => format!(
+                "line {}, col {}: missing '{}' (insert)", line_num, column, ch),
+            Edit::Delete { column, found } => format!(
+                "line {}, col {}: unexpected '{}' (delete)", line_num, column, found),
+            Edit::Replace { column, found, replacement } => format!(
+                "line {}, col {}: expected '{}' but found '{}' (replace)", line_num, column, replacement, found),
+        }
+    }
+}
+
+/// Compiler-style messages for a whole repair script. The closers appended to
+/// the end column of the line are reported together as one completion.
+pub fn edit_diagnostics(edits: &[Edit], line_num: usize, end_column: usize) -> Vec<String> {
+    let trailing = edits.iter().rev().take_while(|edit| matches!(edit, Edit::Insert { column, .. } if *column >= end_column)).count();
+    let (inner, trailing) = edits.split_at(edits.len() - trailing);
+
+    let mut messages = inner.iter().map(|edit| edit.diagnostic(line_num, end_column)).collect::<Vec<_>>();
+    match trailing {
+        [] => (),
+        [edit] => messages.push(edit.diagnostic(line_num, end_column)),
+        _ => {
+            let completion = trailing.iter().filter_map(|edit| match edit {
+                Edit::Insert { ch, .. } => Some(*ch),
+                _ => None,
+            }).collect::<String>();
+            messages.push(format!("line {}, col {}: unexpected end of line, expected \"{}\"", line_num, end_column, completion));
+        }
+    }
+    messages
+}
+
+/// Applies a repair script from `repair_line` to the line it was computed for
+pub fn apply_edits(line: &str, edits: &[Edit]) -> String {
+    let mut repaired = String::with_capacity(line.len() + edits.len());
+    let mut edits = edits.iter().peekable();
+
+    for (idx, ch) in line.chars().enumerate() {
+        let column = idx + 1;
+        let mut keep = true;
+        while let Some(edit) = edits.next_if(|edit| edit.column() == column) {
+            match edit {
+                Edit::Insert { ch, .. } => repaired.push(*ch),
+                Edit::Delete { .. } => keep = false,
+                Edit::Replace { replacement, .. } => {
+                    repaired.push(*replacement);
+                    keep = false;
+                }
+            }
+        }
+        if keep {
+            repaired.push(ch);
+        }
+    }
+
+    for edit in edits {
+        if let Edit::Insert { ch, .. } = edit {
+            repaired.push(*ch);
+        }
+    }
+
+    repaired
+}
+
+/// Finds a minimal set of insertions, deletions and closer replacements that
+/// makes the whole line valid, so every corruption is reported rather than just
+/// the first. Edits are returned in column order. With a `lenience`, only the
+/// delimiters the lenient validator would look at are considered. Lines of more
+/// than `MAX_REPAIR_LEN` delimiters give `None`.
+pub fn repair_line(groupers: &GrouperSet, lenience: Option<&Lenience>, line: &str) -> Result<Option<Vec<Edit>>> {
+    let delimiters = delimiters(groupers, lenience, line)?;
+
+    let chars = delimiters.iter().map(|&(_, ch)| ch).collect::<Vec<_>>();
+    let n = chars.len();
+    if n > MAX_REPAIR_LEN {
+        return Ok(None);
+    }
+    let end_column = code_end(groupers, lenience, line);
+    // Column in the original line of the delimiter at index k
+    let column = |k: usize| delimiters.get(k).map_or(end_column, |&(column, _)| column);
+
+    // cost[i][j] is the fewest edits that make chars[i..j] valid
+    let mut cost = vec![vec![0u32; n + 1]; n + 1];
+    for len in 1..=n {
+        for i in 0..=n - len {
+            let j = i + len;
+            // Dropping chars[i] always works
+            let mut best = cost[i + 1][j] + 1;
+            if let Some(grouper) = groupers.by_open(chars[i]) {
+                for k in i + 1..=j {
+                    // Insert the matching closer before chars[k]
+                    best = best.min(cost[i + 1][k] + 1 + cost[k][j]);
+                    // Pair with chars[k], replacing it if it is the wrong closer
+                    if k < j && groupers.by_close(chars[k]).is_some() {
+                        let replace = u32::from(chars[k] != grouper.close);
+                        best = best.min(cost[i + 1][k] + replace + cost[k + 1][j]);
+                    }
+                }
+            }
+            cost[i][j] = best;
+        }
+    }
+
+    // Walk back through the table. Among equally cheap choices, prefer what the
+    // plain stack check would do: close groupers whose contents are already valid,
+    // otherwise insert closers as late as possible so incomplete lines are
+    // completed at the end. Each edit is tagged with the order it was found in.
+    let mut edits = Vec::new();
+    let mut pending = vec![(0, n)];
+    while let Some((i, j)) = pending.pop() {
+        if i == j {
+            continue;
+        }
+
+        let mut split = None;
+        if let Some(grouper) = groupers.by_open(chars[i]) {
+            let exact_pair = |k: usize| chars[k] == grouper.close && cost[i + 1][k] + cost[k + 1][j] == cost[i][j];
+            if let Some(k) = (i + 1..j).find(|&k| exact_pair(k) && cost[i + 1][k] == 0) {
+                split = Some((k, k + 1));
+            } else if let Some(k) = (i + 1..=j).rev().find(|&k| cost[i + 1][k] + 1 + cost[k][j] == cost[i][j]) {
+                edits.push((edits.len(), Edit::Insert { column: column(k), ch: grouper.close }));
+                split = Some((k, k));
+            } else if let Some(k) = (i + 1..j).find(|&k| exact_pair(k)) {
+                split = Some((k, k + 1));
+            } else if let Some(k) = (i + 1..j).find(|&k| groupers.by_close(chars[k]).is_some() && cost[i + 1][k] + 1 + cost[k + 1][j] == cost[i][j]) {
+                edits.push((edits.len(), Edit::Replace { column: column(k), found: chars[k], replacement: grouper.close }));
+                split = Some((k, k + 1));
+            }
+        }
+
+        match split {
+            Some((inner_end, rest_start)) => {
+                pending.push((i + 1, inner_end));
+                pending.push((rest_start, j));
+            }
+            None => {
+                edits.push((edits.len(), Edit::Delete { column: column(i), found: chars[i] }));
+                pending.push((i + 1, j));
+            }
+        }
+    }
+
+    // Insertions go before the character at their column. Nested groupers that are
+    // closed at the same column were found outermost first, so reverse those.
+    edits.sort_by_key(|(order, edit)| match edit {
+        Edit::Insert { column, .. } => (*column, 0, usize::MAX - order),
+        edit => (edit.column(), 1, *order),
+    });
+    let edits = edits.into_iter().map(|(_, edit)| edit).collect();
+    Ok(Some(edits))
+}
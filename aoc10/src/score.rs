@@ -0,0 +1,220 @@
+use anyhow::{bail, Error, Result};
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+use crate::grouper::GrouperSet;
+use crate::lenient::Lenience;
+use crate::validator::{validate_reader, CheckedLine};
+
+/// A completion score, which is only stored as a `BigUint` when it doesn't fit in
+/// a u64. That keeps the derived ordering correct, as every `Big` is larger than
+/// every `Small`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CompletionScore {
+    Small(u64),
+    Big(BigUint),
+}
+
+impl CompletionScore {
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            CompletionScore::Small(score) => *score as f64,
+            CompletionScore::Big(score) => score.to_f64().unwrap_or(f64::INFINITY),
+        }
+    }
+}
+
+impl From<BigUint> for CompletionScore {
+    fn from(score: BigUint) -> Self {
+        match score.to_u64() {
+            Some(score) => CompletionScore::Small(score),
+            None => CompletionScore::Big(score),
+        }
+    }
+}
+
+impl fmt::Display for CompletionScore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompletionScore::Small(score) => write!(f, "{}", score),
+            CompletionScore::Big(score) => write!(f, "{}", score),
+        }
+    }
+}
+
+/// Running totals for both parts. Only the completion scores of incomplete lines
+/// are kept, since part 2 needs all of them to find the middle one.
+#[derive(Debug, Default)]
+pub struct Scores {
+    syntax_error_total: u64,
+    completion_scores: Vec<CompletionScore>,
+    /// Keep completion scores that overflow a u64 instead of failing
+    big_scores: bool,
+}
+
+impl Scores {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks every line of `reader` and totals the scores. Lines whose completion
+    /// score overflows a u64 are an error.
+    pub fn from_reader<R: Read>(groupers: &GrouperSet, lenience: Option<&Lenience>, reader: R) -> Result<Self> {
+        let mut scores = Self::new();
+        scores.add_reader(groupers, lenience, reader, |_| ())?;
+        Ok(scores)
+    }
+
+    /// Checks every line of `reader` and adds it to the totals, calling `on_line`
+    /// for each checked line first
+    pub fn add_reader<R, F>(&mut self, groupers: &GrouperSet, lenience: Option<&Lenience>, reader: R, mut on_line: F) -> Result<()>
+    where
+        R: Read,
+        F: FnMut(&CheckedLine),
+    {
+        validate_reader(groupers, lenience, reader, |line| {
+            on_line(&line);
+            self.add(groupers, &line)
+        })
+    }
+
+    /// Scores that use arbitrary precision for completion scores too large for a u64
+    pub fn with_big_scores() -> Self {
+        Scores { big_scores: true, ..Self::default() }
+    }
+
+    pub fn add(&mut self, groupers: &GrouperSet, line: &CheckedLine) -> Result<()> {
+        self.syntax_error_total = match self.syntax_error_total.checked_add(line.result.bad_score(groupers)) {
+            Some(total) => total,
+            None => bail!("line {}: syntax error total overflows a u64", line.number),
+        };
+
+        let inc_score = match line.result.inc_score(groupers) {
+            Some(score) => CompletionScore::Small(score),
+            None if self.big_scores => line.result.completion_score(groupers),
+            None => bail!("line {}: completion score overflows a u64", line.number),
+        };
+        if inc_score != CompletionScore::Small(0) {
+            self.completion_scores.push(inc_score);
+        }
+
+        Ok(())
+    }
+
+    /// Part 1 answer
+    pub fn syntax_error_score(&self) -> u64 {
+        self.syntax_error_total
+    }
+
+    /// Completion scores of every incomplete line, in ascending order
+    pub fn sorted_completion_scores(&self) -> Vec<CompletionScore> {
+        let mut inc_scores = self.completion_scores.clone();
+        inc_scores.sort();
+        inc_scores
+    }
+
+    /// Part 2 answer, which the puzzle guarantees has an odd number of scores
+    pub fn middle_completion_score(&self) -> Result<CompletionScore> {
+        self.median_completion_score(Median::Strict)
+    }
+
+    pub fn median_completion_score(&self, median: Median) -> Result<CompletionScore> {
+        let mut inc_scores = self.sorted_completion_scores();
+        if inc_scores.is_empty() {
+            bail!("no completion scores");
+        }
+
+        let mid = inc_scores.len() / 2;
+        let even = inc_scores.len().is_multiple_of(2);
+        match median {
+            Median::Strict if even => bail!("not an odd amount of scores"),
+            Median::Lower if even => Ok(inc_scores.swap_remove(mid - 1)),
+            _ => Ok(inc_scores.swap_remove(mid)),
+        }
+    }
+
+    pub fn mean_completion_score(&self) -> Result<f64> {
+        if self.completion_scores.is_empty() {
+            bail!("no completion scores");
+        }
+
+        let total = self.completion_scores.iter().fold(BigUint::from(0u64), |total, score| match score {
+            CompletionScore::Small(score) => total + *score,
+            CompletionScore::Big(score) => total + score,
+        });
+        Ok(CompletionScore::from(total).to_f64() / self.completion_scores.len() as f64)
+    }
+
+    /// Nearest-rank percentile, `percent` in 0..=100
+    pub fn completion_percentile(&self, percent: f64) -> Result<CompletionScore> {
+        if !(0.0..=100.0).contains(&percent) {
+            bail!("percentile {} is not between 0 and 100", percent);
+        }
+
+        let mut inc_scores = self.sorted_completion_scores();
+        if inc_scores.is_empty() {
+            bail!("no completion scores");
+        }
+
+        let rank = (percent / 100.0 * inc_scores.len() as f64).ceil() as usize;
+        Ok(inc_scores.swap_remove(rank.saturating_sub(1)))
+    }
+
+    /// Each distinct completion score with how many lines had it, in ascending order
+    pub fn completion_histogram(&self) -> Vec<(CompletionScore, usize)> {
+        let mut histogram: Vec<(CompletionScore, usize)> = Vec::new();
+        for score in self.sorted_completion_scores() {
+            match histogram.last_mut() {
+                Some((last, count)) if *last == score => *count += 1,
+                _ => histogram.push((score, 1)),
+            }
+        }
+        histogram
+    }
+}
+
+/// Which middle score to pick when there is an even number of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Median {
+    /// Fail on an even number of scores, as the puzzle never has one
+    Strict,
+    Lower,
+    Upper,
+}
+
+/// How the completion scores are combined for part 2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    Median(Median),
+    Mean,
+    /// The `PERCENTILES` of the scores
+    Percentiles,
+    /// Every distinct score and its count
+    Histogram,
+}
+
+/// Percentiles reported for `Aggregation::Percentiles`
+pub const PERCENTILES: [f64; 7] = [0.0, 10.0, 25.0, 50.0, 75.0, 90.0, 100.0];
+
+impl Default for Aggregation {
+    fn default() -> Self {
+        Aggregation::Median(Median::Lower)
+    }
+}
+
+impl FromStr for Aggregation {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(Aggregation::Median(Median::Strict)),
+            "median" | "lower-median" => Ok(Aggregation::Median(Median::Lower)),
+            "upper-median" => Ok(Aggregation::Median(Median::Upper)),
+            "mean" => Ok(Aggregation::Mean),
+            "percentiles" => Ok(Aggregation::Percentiles),
+            "histogram" => Ok(Aggregation::Histogram),
+            _ => bail!("unknown aggregation {:?}, expected strict, lower-median, upper-median, mean, percentiles or histogram", s),
+        }
+    }
+}
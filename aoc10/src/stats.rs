@@ -0,0 +1,138 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fmt;
+use crate::grouper::GrouperSet;
+use crate::lenient::{delimiters, Lenience};
+use crate::tree::parse_line;
+use crate::validator::LineResult;
+
+/// Statistics for a single line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineStats {
+    pub result: LineResult,
+    /// Deepest nesting reached, up to the first corruption
+    pub max_depth: usize,
+    /// Openers of each grouper in the line, in grouper table order
+    pub opened: Vec<usize>,
+    /// Closers of each grouper in the line, in grouper table order
+    pub closed: Vec<usize>,
+}
+
+impl LineStats {
+    pub fn of(groupers: &GrouperSet, lenience: Option<&Lenience>, line: &str) -> Result<Self> {
+        let tree = parse_line(groupers, lenience, line)?;
+        let mut opened = vec![0; groupers.groupers().len()];
+        let mut closed = vec![0; groupers.groupers().len()];
+
+        for (_, ch) in delimiters(groupers, lenience, line)? {
+            if let Some(idx) = groupers.groupers().iter().position(|grouper| grouper.open == ch) {
+                opened[idx] += 1;
+            } else if let Some(idx) = groupers.groupers().iter().position(|grouper| grouper.close == ch) {
+                closed[idx] += 1;
+            }
+        }
+
+        Ok(LineStats { max_depth: tree.max_depth(), result: tree.result, opened, closed })
+    }
+
+    /// Summary on one line, e.g. `incomplete, depth 10, ( 5/3, [ 5/2, { 3/0, < 3/3`,
+    /// where each grouper is followed by its opened/closed counts
+    pub fn describe(&self, groupers: &GrouperSet) -> String {
+        let mut text = format!("{}, depth {}", status_name(&self.result), self.max_depth);
+        for (idx, grouper) in groupers.groupers().iter().enumerate() {
+            text += &format!(", {} {}/{}", grouper.open, self.opened[idx], self.closed[idx]);
+        }
+        text
+    }
+}
+
+fn status_name(result: &LineResult) -> &'static str {
+    match result {
+        LineResult::Valid => "valid",
+        LineResult::Corrupted { .. } => "corrupted",
+        LineResult::Incomplete { .. } => "incomplete",
+    }
+}
+
+/// Statistics over a whole input, built up one line at a time. Displays as a
+/// multi-line report.
+#[derive(Debug, Clone)]
+pub struct CorpusStats<'a> {
+    groupers: &'a GrouperSet,
+    pub lines: usize,
+    pub valid: usize,
+    pub corrupted: usize,
+    pub incomplete: usize,
+    pub max_depth: usize,
+    pub total_depth: usize,
+    pub opened: Vec<usize>,
+    pub closed: Vec<usize>,
+    /// Number of incomplete lines for each completion length
+    pub completion_lengths: BTreeMap<usize, usize>,
+}
+
+impl<'a> CorpusStats<'a> {
+    pub fn new(groupers: &'a GrouperSet) -> Self {
+        CorpusStats {
+            groupers,
+            lines: 0,
+            valid: 0,
+            corrupted: 0,
+            incomplete: 0,
+            max_depth: 0,
+            total_depth: 0,
+            opened: vec![0; groupers.groupers().len()],
+            closed: vec![0; groupers.groupers().len()],
+            completion_lengths: BTreeMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, line: &LineStats) {
+        self.lines += 1;
+        match &line.result {
+            LineResult::Valid => self.valid += 1,
+            LineResult::Corrupted { .. } => self.corrupted += 1,
+            LineResult::Incomplete { missing_closers } => {
+                self.incomplete += 1;
+                *self.completion_lengths.entry(missing_closers.len()).or_insert(0) += 1;
+            }
+        }
+
+        self.max_depth = self.max_depth.max(line.max_depth);
+        self.total_depth += line.max_depth;
+        for (total, count) in self.opened.iter_mut().zip(&line.opened) {
+            *total += count;
+        }
+        for (total, count) in self.closed.iter_mut().zip(&line.closed) {
+            *total += count;
+        }
+    }
+
+    fn percent(&self, count: usize) -> f64 {
+        if self.lines == 0 {
+            0.0
+        } else {
+            count as f64 * 100.0 / self.lines as f64
+        }
+    }
+}
+
+impl fmt::Display for CorpusStats<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "lines: {}", self.lines)?;
+        writeln!(f, "valid: {} ({:.1}%)", self.valid, self.percent(self.valid))?;
+        writeln!(f, "corrupted: {} ({:.1}%)", self.corrupted, self.percent(self.corrupted))?;
+        writeln!(f, "incomplete: {} ({:.1}%)", self.incomplete, self.percent(self.incomplete))?;
+
+        let mean_depth = if self.lines == 0 { 0.0 } else { self.total_depth as f64 / self.lines as f64 };
+        writeln!(f, "max depth: {} (mean per line {:.2})", self.max_depth, mean_depth)?;
+
+        for (idx, grouper) in self.groupers.groupers().iter().enumerate() {
+            writeln!(f, "{}{}: opened {}, closed {}", grouper.open, grouper.close, self.opened[idx], self.closed[idx])?;
+        }
+        for (len, count) in &self.completion_lengths {
+            writeln!(f, "completion length {}: {} lines", len, count)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use anyhow::Result;
+use std::fmt;
+use crate::grouper::GrouperSet;
+use crate::lenient::{delimiters, Lenience};
+use crate::validator::{line_result, match_delimiter, LineResult, Matched};
+
+/// A grouper and everything nested inside it. Columns are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupNode {
+    pub open: char,
+    /// Closer the grouper needs, whether or not the line has it
+    pub close: char,
+    pub start_column: usize,
+    /// Column of the closer, or None if the line ends or is corrupted first
+    pub end_column: Option<usize>,
+    pub children: Vec<GroupNode>,
+}
+
+impl GroupNode {
+    /// Nesting depth of this node, counting itself
+    pub fn depth(&self) -> usize {
+        1 + self.children.iter().map(GroupNode::depth).max().unwrap_or(0)
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter, indent: usize) -> fmt::Result {
+        match self.end_column {
+            Some(end_column) => writeln!(f, "{:indent$}{}{} {}-{}", "", self.open, self.close, self.start_column, end_column, indent = indent)?,
+            None => writeln!(f, "{:indent$}{}{} {}- (unclosed)", "", self.open, self.close, self.start_column, indent = indent)?,
+        }
+        for child in &self.children {
+            child.fmt_indented(f, indent + 2)?;
+        }
+        Ok(())
+    }
+}
+
+/// Nesting structure of a line. Incomplete and corrupted lines give a partial
+/// tree, where groupers still open at the end of the line or at the first
+/// corruption are left unclosed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTree {
+    pub roots: Vec<GroupNode>,
+    pub result: LineResult,
+}
+
+impl ParseTree {
+    pub fn max_depth(&self) -> usize {
+        self.roots.iter().map(GroupNode::depth).max().unwrap_or(0)
+    }
+}
+
+/// Renders each node on its own line as its grouper pair and column span,
+/// indented two spaces per nesting level
+impl fmt::Display for ParseTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for root in &self.roots {
+            root.fmt_indented(f, 0)?;
+        }
+        Ok(())
+    }
+}
+
+/// Builds the nesting tree of a line, along with the same result `eval_line` gives
+pub fn parse_line(groupers: &GrouperSet, lenience: Option<&Lenience>, line: &str) -> Result<ParseTree> {
+    let mut roots = Vec::new();
+    let mut open_nodes: Vec<GroupNode> = Vec::new();
+    let mut corruption = None;
+
+    for (column, ch) in delimiters(groupers, lenience, line)? {
+        let innermost = open_nodes.last().map(|node| node.close);
+        match match_delimiter(groupers, innermost, column, ch) {
+            Some(Matched::Open(grouper)) => open_nodes.push(GroupNode {
+                open: grouper.open,
+                close: grouper.close,
+                start_column: column,
+                end_column: None,
+                children: Vec::new(),
+            }),
+            Some(Matched::Close) => {
+                let mut node = open_nodes.pop().unwrap();
+                node.end_column = Some(column);
+                match open_nodes.last_mut() {
+                    Some(parent) => parent.children.push(node),
+                    None => roots.push(node),
+                }
+            }
+            Some(Matched::Corrupted(result)) => {
+                corruption = Some(result);
+                break;
+            }
+            // `delimiters` only gives delimiters
+            None => unreachable!(),
+        }
+    }
+
+    let result = line_result(corruption, open_nodes.iter().map(|node| node.close));
+
+    // Attach whatever is still open to its parent, innermost first
+    while let Some(node) = open_nodes.pop() {
+        match open_nodes.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => roots.push(node),
+        }
+    }
+
+    Ok(ParseTree { roots, result })
+}
@@ -0,0 +1,357 @@
+use anyhow::{anyhow, bail, Result};
+use std::io::Read;
+use crate::grouper::{Grouper, GrouperSet};
+use crate::lenient::{DelimiterFilter, Lenience};
+use crate::score::CompletionScore;
+use num_bigint::BigUint;
+
+/// Outcome of checking a single line. Columns are 1-based and count characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineResult {
+    Valid,
+    Corrupted {
+        column: usize,
+        /// Closer that would have matched the innermost open grouper, if any
+        expected: Option<char>,
+        found: char,
+    },
+    Incomplete {
+        /// Closers needed to complete the line, innermost first
+        missing_closers: Vec<char>,
+    },
+}
+
+impl LineResult {
+    /// Part 1 score, nonzero only for corrupted lines
+    pub fn bad_score(&self, groupers: &GrouperSet) -> u64 {
+        match self {
+            LineResult::Corrupted { found, .. } => groupers.by_close(*found).map_or(0, |grouper| grouper.bad_score),
+            _ => 0,
+        }
+    }
+
+    /// Part 2 score, nonzero only for incomplete lines, or None if it doesn't fit in a u64
+    pub fn inc_score(&self, groupers: &GrouperSet) -> Option<u64> {
+        match self {
+            LineResult::Incomplete { missing_closers } => missing_closers.iter()
+                .filter_map(|&close| groupers.by_close(close))
+                .try_fold(0u64, |accum, grouper| accum.checked_mul(5)?.checked_add(grouper.inc_score)),
+            _ => Some(0),
+        }
+    }
+
+    /// Part 2 score, falling back to arbitrary precision when it doesn't fit in a u64
+    pub fn completion_score(&self, groupers: &GrouperSet) -> CompletionScore {
+        if let Some(score) = self.inc_score(groupers) {
+            return CompletionScore::Small(score);
+        }
+
+        match self {
+            LineResult::Incomplete { missing_closers } => missing_closers.iter()
+                .filter_map(|&close| groupers.by_close(close))
+                .fold(BigUint::from(0u64), |accum, grouper| accum * 5u64 + grouper.inc_score)
+                .into(),
+            _ => CompletionScore::Small(0),
+        }
+    }
+
+    /// Closing string that would complete an incomplete line
+    pub fn completion(&self) -> Option<String> {
+        match self {
+            LineResult::Incomplete { missing_closers } => Some(missing_closers.iter().collect()),
+            _ => None,
+        }
+    }
+
+    /// Compiler-style message for a line that is not valid, where the line's
+    /// code ends before `end_column`
+    pub fn diagnostic(&self, line_num: usize, end_column: usize) -> Option<String> {
+        match self {
+            LineResult::Valid => None,
+            LineResult::Corrupted { column, expected: Some(expected), found } => Some(format!(
+                "line {}, col {}: expected '{}' but found '{}'",
+                line_num, column, expected, found)),
+            LineResult::Corrupted { column, expected: None, found } => Some(format!(
+                "line {}, col {}: unexpected '{}' with no open grouper",
+                line_num, column, found)),
+            LineResult::Incomplete { missing_closers } => Some(format!(
+                "line {}, col {}: unexpected end of line, expected '{}'",
+                line_num, end_column, missing_closers[0])),
+        }
+    }
+}
+
+/// What a delimiter does to the groupers open before it
+pub(crate) enum Matched<'a> {
+    Open(&'a Grouper),
+    /// Closes the innermost open grouper
+    Close,
+    Corrupted(LineResult),
+}
+
+/// Matches the character at `column` against the innermost open grouper, which
+/// needs the closer `innermost`. Returns `None` if `ch` is not a delimiter.
+/// Shared by `BracketValidator` and `parse_line` so they agree on every line.
+pub(crate) fn match_delimiter<'a>(groupers: &'a GrouperSet, innermost: Option<char>, column: usize, ch: char) -> Option<Matched<'a>> {
+    if let Some(grouper) = groupers.by_open(ch) {
+        Some(Matched::Open(grouper))
+    } else if groupers.by_close(ch).is_some() {
+        if innermost == Some(ch) {
+            Some(Matched::Close)
+        } else {
+            Some(Matched::Corrupted(LineResult::Corrupted { column, expected: innermost, found: ch }))
+        }
+    } else {
+        None
+    }
+}
+
+/// Result of a line given its first corruption, if any, and the closers of the
+/// groupers still open at its end, outermost first
+pub(crate) fn line_result(corruption: Option<LineResult>, open_closers: impl DoubleEndedIterator<Item = char>) -> LineResult {
+    let missing_closers = open_closers.rev().collect::<Vec<_>>();
+    match corruption {
+        Some(corruption) => corruption,
+        None if missing_closers.is_empty() => LineResult::Valid,
+        None => LineResult::Incomplete { missing_closers },
+    }
+}
+
+/// A finished line from `BracketValidator`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckedLine {
+    /// 1-based line number
+    pub number: usize,
+    /// Length of the line in characters, not counting the newline
+    pub length: usize,
+    /// Column the closers that complete the line go at. That is `length + 1`,
+    /// except in lenient mode where the line ends in a skip region.
+    pub end_column: usize,
+    pub result: LineResult,
+}
+
+/// Incremental checker that can be fed input in arbitrary chunks. Lines end at
+/// `\n` and may span any number of pushes, and UTF-8 sequences may be split
+/// between pushes. Memory use is bounded by the nesting depth of a line.
+pub struct BracketValidator<'a> {
+    groupers: &'a GrouperSet,
+    grouper_stack: Vec<&'a Grouper>,
+    /// First corruption in the current line; the rest of the line is skipped
+    corruption: Option<LineResult>,
+    line_num: usize,
+    column: usize,
+    /// Bytes of a partially received UTF-8 sequence
+    pending: [u8; 4],
+    pending_len: usize,
+    /// Set in lenient mode to drop everything that isn't a delimiter
+    filter: Option<DelimiterFilter<'a>>,
+    /// Scratch space for delimiters released by `filter`
+    delimiters: Vec<(usize, char)>,
+}
+
+impl<'a> BracketValidator<'a> {
+    pub fn new(groupers: &'a GrouperSet) -> Self {
+        BracketValidator {
+            groupers,
+            grouper_stack: Vec::new(),
+            corruption: None,
+            line_num: 1,
+            column: 0,
+            pending: [0; 4],
+            pending_len: 0,
+            filter: None,
+            delimiters: Vec::new(),
+        }
+    }
+
+    /// Validator that skips characters that are not delimiters, as well as the
+    /// skip regions in `lenience`, instead of failing on them
+    pub fn lenient(groupers: &'a GrouperSet, lenience: &'a Lenience) -> Self {
+        BracketValidator {
+            filter: Some(DelimiterFilter::new(groupers, lenience)),
+            ..Self::new(groupers)
+        }
+    }
+
+    /// Numbers lines starting from `line_num` instead of 1, for checking part of an input
+    pub fn with_first_line(mut self, line_num: usize) -> Self {
+        self.line_num = line_num;
+        self
+    }
+
+    /// Feeds one byte, returning the checked line when the byte is a newline
+    pub fn push_byte(&mut self, b: u8) -> Result<Option<CheckedLine>> {
+        if b == b'\n' {
+            return self.finish_line().map(Some);
+        }
+
+        self.pending[self.pending_len] = b;
+        self.pending_len += 1;
+
+        let expected_len = match self.pending[0] {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => 0,
+        };
+        if self.pending_len < expected_len {
+            return Ok(None);
+        }
+
+        let ch = std::str::from_utf8(&self.pending[..self.pending_len]).ok().and_then(|s| s.chars().next());
+        self.pending_len = 0;
+        match ch {
+            Some(ch) => {
+                self.push_char(ch)?;
+                Ok(None)
+            }
+            None => bail!("line {}, col {}: invalid UTF-8", self.line_num, self.column + 1),
+        }
+    }
+
+    /// Feeds a chunk of bytes, returning every line it finished
+    pub fn push_bytes(&mut self, chunk: &[u8]) -> Result<Vec<CheckedLine>> {
+        let mut lines = Vec::new();
+        for &b in chunk {
+            if let Some(line) = self.push_byte(b)? {
+                lines.push(line);
+            }
+        }
+        Ok(lines)
+    }
+
+    /// Feeds one character of the current line. Newlines are not treated
+    /// specially here; use `end_line` to finish a line.
+    pub fn push_char(&mut self, ch: char) -> Result<()> {
+        self.column += 1;
+
+        match &mut self.filter {
+            Some(filter) => {
+                filter.push(self.column, ch, &mut self.delimiters);
+                self.drain_delimiters();
+            }
+            None => {
+                if !self.push_delimiter(self.column, ch) {
+                    bail!("line {}, col {}: unknown character {:?}", self.line_num, self.column, ch);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks a delimiter, returning false if `ch` is not one
+    fn push_delimiter(&mut self, column: usize, ch: char) -> bool {
+        if self.corruption.is_some() {
+            return true;
+        }
+
+        let innermost = self.grouper_stack.last().map(|grouper| grouper.close);
+        match match_delimiter(self.groupers, innermost, column, ch) {
+            Some(Matched::Open(grouper)) => self.grouper_stack.push(grouper),
+            Some(Matched::Close) => {
+                self.grouper_stack.pop();
+            }
+            Some(Matched::Corrupted(corruption)) => self.corruption = Some(corruption),
+            None => return false,
+        }
+
+        true
+    }
+
+    fn drain_delimiters(&mut self) {
+        let mut delimiters = std::mem::take(&mut self.delimiters);
+        for &(column, ch) in &delimiters {
+            self.push_delimiter(column, ch);
+        }
+        delimiters.clear();
+        self.delimiters = delimiters;
+    }
+
+    /// Finishes the current line and starts the next one
+    pub fn end_line(&mut self) -> CheckedLine {
+        let end_column = match &mut self.filter {
+            Some(filter) => {
+                let end_column = filter.end_line(&mut self.delimiters);
+                self.drain_delimiters();
+                end_column
+            }
+            None => self.column + 1,
+        };
+
+        let result = line_result(self.corruption.take(), self.grouper_stack.iter().map(|grouper| grouper.close));
+
+        let line = CheckedLine { number: self.line_num, length: self.column, end_column, result };
+        self.grouper_stack.clear();
+        self.line_num += 1;
+        self.column = 0;
+        line
+    }
+
+    /// Like `end_line`, but fails if the line ended partway through a UTF-8 sequence
+    pub fn finish_line(&mut self) -> Result<CheckedLine> {
+        if self.pending_len != 0 {
+            bail!("line {}, col {}: truncated UTF-8 sequence", self.line_num, self.column + 1);
+        }
+
+        Ok(self.end_line())
+    }
+
+    /// Finishes the input. A last line without a trailing newline is returned;
+    /// an input ending in a newline has no extra empty line.
+    pub fn finish(mut self) -> Result<Option<CheckedLine>> {
+        if self.column == 0 && self.pending_len == 0 {
+            Ok(None)
+        } else {
+            self.finish_line().map(Some)
+        }
+    }
+}
+
+/// Checks a single line, which should not contain a newline. With a
+/// `lenience`, characters that are not delimiters are skipped.
+pub fn eval_line(groupers: &GrouperSet, lenience: Option<&Lenience>, line: &str) -> Result<LineResult> {
+    let mut validator = match lenience {
+        Some(lenience) => BracketValidator::lenient(groupers, lenience),
+        None => BracketValidator::new(groupers),
+    };
+    for ch in line.chars() {
+        validator.push_char(ch)?;
+    }
+
+    Ok(validator.end_line().result)
+}
+
+/// Streams `reader` through a `BracketValidator`, calling `on_line` for every line
+pub fn validate_reader<R, F>(groupers: &GrouperSet, lenience: Option<&Lenience>, mut reader: R, mut on_line: F) -> Result<()>
+where
+    R: Read,
+    F: FnMut(CheckedLine) -> Result<()>,
+{
+    let mut validator = match lenience {
+        Some(lenience) => BracketValidator::lenient(groupers, lenience),
+        None => BracketValidator::new(groupers),
+    };
+    let mut buf = [0u8; 64 * 1024];
+
+    loop {
+        let len = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(len) => len,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(anyhow!(err)),
+        };
+        for &b in &buf[..len] {
+            if let Some(line) = validator.push_byte(b)? {
+                on_line(line)?;
+            }
+        }
+    }
+
+    if let Some(line) = validator.finish()? {
+        on_line(line)?;
+    }
+
+    Ok(())
+}
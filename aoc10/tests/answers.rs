@@ -0,0 +1,83 @@
+use aoc10::{Aggregation, CompletionScore, GrouperSet, Median, Scores};
+use std::fs::File;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn input_path(name: &str) -> String {
+    format!("{}/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+fn scores_for(name: &str) -> Scores {
+    let file = File::open(input_path(name)).unwrap();
+    Scores::from_reader(&GrouperSet::default(), None, file).unwrap()
+}
+
+#[test]
+fn sample_part1() {
+    assert_eq!(scores_for("aoc10-sample.txt").syntax_error_score(), 26397);
+}
+
+/// The puzzle's answer for the sample, which folds each line's missing closers
+/// innermost first
+#[test]
+fn sample_part2() {
+    let scores = scores_for("aoc10-sample.txt");
+    assert_eq!(scores.middle_completion_score().unwrap(), CompletionScore::Small(288957));
+    assert_eq!(scores.median_completion_score(Median::Lower).unwrap(), CompletionScore::Small(288957));
+    assert_eq!(scores.median_completion_score(Median::Upper).unwrap(), CompletionScore::Small(288957));
+}
+
+#[test]
+fn input_part1() {
+    assert_eq!(scores_for("aoc10.txt").syntax_error_score(), 299793);
+}
+
+#[test]
+fn input_part2() {
+    assert_eq!(scores_for("aoc10.txt").middle_completion_score().unwrap(), CompletionScore::Small(3654963618));
+}
+
+#[test]
+fn even_score_count_needs_a_non_strict_median() {
+    // Drop the last incomplete line of the sample, leaving four completion scores
+    let sample = std::fs::read_to_string(input_path("aoc10-sample.txt")).unwrap();
+    let lines = sample.lines().take(9).collect::<Vec<_>>().join("\n");
+    let scores = Scores::from_reader(&GrouperSet::default(), None, lines.as_bytes()).unwrap();
+
+    assert!(scores.middle_completion_score().is_err());
+    assert_eq!(scores.median_completion_score(Median::Lower).unwrap(), CompletionScore::Small(288957));
+    assert_eq!(scores.median_completion_score(Median::Upper).unwrap(), CompletionScore::Small(995444));
+    assert_eq!("strict".parse::<Aggregation>().unwrap(), Aggregation::Median(Median::Strict));
+}
+
+/// Runs the binary on `input`, returning whether it succeeded and its stdout
+fn run_binary(args: &[&str], input: &[u8]) -> (bool, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_aoc10"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    (output.status.success(), String::from_utf8(output.stdout).unwrap())
+}
+
+#[test]
+fn binary_reports_both_parts() {
+    let sample = std::fs::read(input_path("aoc10-sample.txt")).unwrap();
+    assert_eq!(
+        run_binary(&["--part", "both"], &sample),
+        (true, "part 1 syntax error score is 26397\npart 2 completion score is 288957\n".to_string()),
+    );
+    assert_eq!(run_binary(&["--part", "2"], &sample), (true, "final score is 288957\n".to_string()));
+}
+
+#[test]
+fn grouper_specs_add_to_the_default_table() {
+    let input = "«(»\n(«»\n".as_bytes();
+    assert_eq!(run_binary(&["--part", "1", "--grouper", "« » 5 7"], input), (true, "final score is 7\n".to_string()));
+    // A spec that reuses one of the default characters clashes with the default grouper
+    assert!(!run_binary(&["--part", "1", "--grouper", "( ] 5 7"], input).0);
+}
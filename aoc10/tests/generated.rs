@@ -0,0 +1,176 @@
+use aoc10::{
+    apply_edits, check_lines, check_lines_parallel, eval_line, parse_line, repair_line, run_every_checker,
+    BracketValidator, Grouper, GrouperSet, Lenience, LineGenerator, LineResult, SplitMix64,
+};
+
+const CASES: u64 = 500;
+
+fn unicode_groupers() -> GrouperSet {
+    GrouperSet::new(vec![
+        Grouper { open: '«', close: '»', inc_score: 1, bad_score: 2 },
+        Grouper { open: '⟨', close: '⟩', inc_score: 2, bad_score: 5 },
+        Grouper { open: '(', close: ')', inc_score: 3, bad_score: 7 },
+    ]).unwrap()
+}
+
+fn for_each_grouper_set<F: FnMut(&GrouperSet)>(mut f: F) {
+    f(&GrouperSet::default());
+    f(&unicode_groupers());
+}
+
+#[test]
+fn valid_lines_are_valid() {
+    for_each_grouper_set(|groupers| {
+        for seed in 0..CASES {
+            let line = LineGenerator::new(groupers, seed).valid();
+            assert_eq!(eval_line(groupers, None, &line.text).unwrap(), LineResult::Valid, "{:?}", line.text);
+        }
+    });
+}
+
+#[test]
+fn incomplete_lines_report_their_completion() {
+    for_each_grouper_set(|groupers| {
+        for seed in 0..CASES {
+            let line = LineGenerator::new(groupers, seed).incomplete();
+            assert_eq!(eval_line(groupers, None, &line.text).unwrap(), line.expected, "{:?}", line.text);
+        }
+    });
+}
+
+#[test]
+fn zero_limits_still_generate_lines() {
+    let groupers = GrouperSet::default();
+    for seed in 0..CASES {
+        let mut generator = LineGenerator::new(&groupers, seed);
+        generator.max_depth = 0;
+        generator.max_width = 0;
+        let line = generator.any();
+        assert_eq!(eval_line(&groupers, None, &line.text).unwrap(), line.expected, "{:?}", line.text);
+    }
+}
+
+#[test]
+fn corrupted_lines_report_the_injected_corruption() {
+    for_each_grouper_set(|groupers| {
+        for seed in 0..CASES {
+            let line = LineGenerator::new(groupers, seed).corrupted();
+            assert_eq!(eval_line(groupers, None, &line.text).unwrap(), line.expected, "{:?}", line.text);
+        }
+    });
+}
+
+#[test]
+fn streaming_in_random_chunks_matches_eval_line() {
+    for_each_grouper_set(|groupers| {
+        let mut generator = LineGenerator::new(groupers, 1);
+        let lines = (0..CASES).map(|_| generator.any()).collect::<Vec<_>>();
+        let input = lines.iter().map(|line| format!("{}\n", line.text)).collect::<String>();
+
+        let mut rng = SplitMix64::new(2);
+        let mut validator = BracketValidator::new(groupers);
+        let mut checked = Vec::new();
+        let mut rest = input.as_bytes();
+        while !rest.is_empty() {
+            // Chunk boundaries land inside multi-byte characters too
+            let (chunk, tail) = rest.split_at((1 + rng.below(16)).min(rest.len()));
+            checked.extend(validator.push_bytes(chunk).unwrap());
+            rest = tail;
+        }
+        assert_eq!(validator.finish().unwrap(), None);
+
+        assert_eq!(checked.len(), lines.len());
+        for (idx, (checked, line)) in checked.iter().zip(&lines).enumerate() {
+            assert_eq!(checked.number, idx + 1);
+            assert_eq!(checked.length, line.text.chars().count());
+            assert_eq!(checked.result, line.expected, "{:?}", line.text);
+        }
+    });
+}
+
+#[test]
+fn parallel_matches_sequential() {
+    let groupers = GrouperSet::default();
+    let mut generator = LineGenerator::new(&groupers, 3);
+    let lines = (0..CASES).map(|_| generator.any().text.into_bytes()).collect::<Vec<_>>();
+
+    let sequential = check_lines(&groupers, None, 1, &lines).unwrap();
+    for threads in [1, 2, 3, 7] {
+        assert_eq!(check_lines_parallel(&groupers, None, 1, &lines, threads).unwrap(), sequential);
+    }
+}
+
+#[test]
+fn repairs_are_valid_and_no_larger_than_needed() {
+    for_each_grouper_set(|groupers| {
+        for seed in 0..CASES {
+            // Keep lines short, as repairing is cubic in the line length
+            let mut generator = LineGenerator::new(groupers, seed);
+            generator.max_depth = 3;
+            let line = generator.any();
+            let edits = repair_line(groupers, None, &line.text).unwrap().unwrap();
+            let repaired = apply_edits(&line.text, &edits);
+            assert_eq!(eval_line(groupers, None, &repaired).unwrap(), LineResult::Valid, "{:?} -> {:?}", line.text, repaired);
+
+            match &line.expected {
+                LineResult::Valid => assert!(edits.is_empty()),
+                LineResult::Incomplete { missing_closers } => assert_eq!(edits.len(), missing_closers.len()),
+                LineResult::Corrupted { .. } => assert!(!edits.is_empty()),
+            }
+        }
+    });
+}
+
+#[test]
+fn lenient_repairs_recheck_as_valid() {
+    let groupers = GrouperSet::default();
+    let lenience = Lenience { skip_regions: vec!["\" \" \\".parse().unwrap(), "// $".parse().unwrap()] };
+    let alphabet = ['(', ')', '[', ']', '{', '}', '<', '>', ' ', 'x'];
+    let mut rng = SplitMix64::new(5);
+
+    for _ in 0..CASES {
+        // Code with delimiters inside a string, and sometimes a trailing comment
+        let mut line = (0..rng.below(24)).map(|_| alphabet[rng.below(alphabet.len())]).collect::<String>();
+        let at = line.char_indices().nth(rng.below(line.len() + 1)).map_or(line.len(), |(idx, _)| idx);
+        line.insert_str(at, "\"(\\\"]\"");
+        if rng.below(2) == 0 {
+            line.push_str(" // note (");
+        }
+
+        let edits = repair_line(&groupers, Some(&lenience), &line).unwrap().unwrap();
+        let repaired = apply_edits(&line, &edits);
+        assert_eq!(eval_line(&groupers, Some(&lenience), &repaired).unwrap(), LineResult::Valid, "{:?} -> {:?}", line, repaired);
+
+        // Completions go after the string but before the comment and any space ahead of it
+        let code = line.strip_suffix(" // note (").unwrap_or(&line).trim_end();
+        let end_column = code.chars().count() + 1;
+        assert!(edits.iter().all(|edit| edit.column() <= end_column), "{:?} -> {:?}", line, repaired);
+        if let LineResult::Incomplete { missing_closers } = eval_line(&groupers, Some(&lenience), &line).unwrap() {
+            let completion = missing_closers.iter().collect::<String>();
+            assert_eq!(repaired, format!("{}{}{}", code, completion, &line[code.len()..]), "{:?}", line);
+        }
+    }
+}
+
+#[test]
+fn parse_tree_agrees_with_eval_line() {
+    for_each_grouper_set(|groupers| {
+        for seed in 0..CASES {
+            let line = LineGenerator::new(groupers, seed).any();
+            let tree = parse_line(groupers, None, &line.text).unwrap();
+            assert_eq!(tree.result, line.expected, "{:?}", line.text);
+        }
+    });
+}
+
+/// Pseudo-random bytes through the same checks as the `validate` fuzz target
+#[test]
+fn arbitrary_bytes_do_not_panic() {
+    let alphabet = b"()[]{}<>\"/\\ \r\nx\xc3\xa9\xf0\x9f\x98\x80\xff";
+    let mut rng = SplitMix64::new(4);
+
+    for _ in 0..CASES {
+        let data = (0..rng.below(64)).map(|_| alphabet[rng.below(alphabet.len())]).collect::<Vec<_>>();
+        run_every_checker(&data);
+    }
+}
@@ -0,0 +1,50 @@
+use aoc10::{edit_diagnostics, repair_line, GrouperSet, MAX_REPAIR_LEN};
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn run_binary(args: &[&str], input: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_aoc10"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn completions_are_one_diagnostic() {
+    let line = "[({(<(())[]>[[{[]{<()<>>";
+    let edits = repair_line(&GrouperSet::default(), None, line).unwrap().unwrap();
+    assert_eq!(edits.len(), 8);
+    assert_eq!(
+        edit_diagnostics(&edits, 1, line.len() + 1),
+        vec!["line 1, col 25: unexpected end of line, expected \"}}]])})]\"".to_string()],
+    );
+}
+
+#[test]
+fn long_lines_fall_back_to_the_first_corruption() {
+    let line = format!("{}]", "(".repeat(MAX_REPAIR_LEN));
+    assert_eq!(repair_line(&GrouperSet::default(), None, &line).unwrap(), None);
+
+    let input = format!("{}\n(]\n", line);
+    let output = run_binary(&["--recover", "--diagnostics", "--part", "1"], input.as_bytes());
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        format!("line 1, col {}: expected ')' but found ']'\nline 2, col 2: expected ')' but found ']' (replace)\n", MAX_REPAIR_LEN + 1),
+    );
+}
+
+#[test]
+fn lenient_completions_go_before_a_trailing_comment() {
+    let lenient = ["--lenient", "--skip", "// $", "--skip", "\" \" \\", "--part", "1"];
+    for recover in [&[][..], &["--recover"][..]] {
+        let args = [&lenient[..], &["--repair"], recover].concat();
+        let output = run_binary(&args, b"f(a, [b // note\nf(\"abc\"\nx = [1, \"a\" // c\n");
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "f(a, [b]) // note\nf(\"abc\")\nx = [1, \"a\"] // c\n");
+    }
+}
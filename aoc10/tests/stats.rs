@@ -0,0 +1,42 @@
+use aoc10::{CorpusStats, GrouperSet, LineResult, LineStats};
+
+const SAMPLE_LINES: [&str; 2] = ["[({(<(())[]>[[{[]{<()<>>", "[(()[<>])]({[<{<<[]>>("];
+
+#[test]
+fn line_stats_count_each_grouper() {
+    let groupers = GrouperSet::default();
+    let stats = LineStats::of(&groupers, None, SAMPLE_LINES[0]).unwrap();
+    assert_eq!(stats.result, LineResult::Incomplete { missing_closers: "}}]])})]".chars().collect() });
+    assert_eq!(stats.max_depth, 10);
+    assert_eq!(stats.opened, [5, 5, 3, 3]);
+    assert_eq!(stats.closed, [3, 2, 0, 3]);
+    assert_eq!(stats.describe(&groupers), "incomplete, depth 10, ( 5/3, [ 5/2, { 3/0, < 3/3");
+}
+
+#[test]
+fn corpus_stats_report() {
+    let groupers = GrouperSet::default();
+    let mut corpus = CorpusStats::new(&groupers);
+    for line in SAMPLE_LINES {
+        corpus.add(&LineStats::of(&groupers, None, line).unwrap());
+    }
+
+    assert_eq!((corpus.lines, corpus.valid, corpus.corrupted, corpus.incomplete), (2, 0, 0, 2));
+    assert_eq!((corpus.max_depth, corpus.total_depth), (10, 18));
+    assert_eq!(corpus.opened, [9, 9, 5, 7]);
+    assert_eq!(corpus.closed, [5, 5, 0, 6]);
+    assert_eq!(corpus.completion_lengths.iter().map(|(&len, &count)| (len, count)).collect::<Vec<_>>(), [(6, 1), (8, 1)]);
+    assert_eq!(corpus.to_string(), concat!(
+        "lines: 2\n",
+        "valid: 0 (0.0%)\n",
+        "corrupted: 0 (0.0%)\n",
+        "incomplete: 2 (100.0%)\n",
+        "max depth: 10 (mean per line 9.00)\n",
+        "(): opened 9, closed 5\n",
+        "[]: opened 9, closed 5\n",
+        "{}: opened 5, closed 0\n",
+        "<>: opened 7, closed 6\n",
+        "completion length 6: 1 lines\n",
+        "completion length 8: 1 lines\n",
+    ));
+}
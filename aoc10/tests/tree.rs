@@ -0,0 +1,61 @@
+use aoc10::{parse_line, GroupNode, GrouperSet, LineResult};
+
+fn node(open: char, close: char, start_column: usize, end_column: Option<usize>, children: Vec<GroupNode>) -> GroupNode {
+    GroupNode { open, close, start_column, end_column, children }
+}
+
+#[test]
+fn tree_nests_groupers_by_column() {
+    let tree = parse_line(&GrouperSet::default(), None, "(<>[{}").unwrap();
+    assert_eq!(
+        tree.roots,
+        [node('(', ')', 1, None, vec![
+            node('<', '>', 2, Some(3), vec![]),
+            node('[', ']', 4, None, vec![node('{', '}', 5, Some(6), vec![])]),
+        ])],
+    );
+    assert_eq!(tree.result, LineResult::Incomplete { missing_closers: vec![']', ')'] });
+}
+
+#[test]
+fn sample_line_tree() {
+    let tree = parse_line(&GrouperSet::default(), None, "[({(<(())[]>[[{[]{<()<>>").unwrap();
+    assert_eq!(tree.max_depth(), 10);
+    assert_eq!(tree.to_string(), concat!(
+        "[] 1- (unclosed)\n",
+        "  () 2- (unclosed)\n",
+        "    {} 3- (unclosed)\n",
+        "      () 4- (unclosed)\n",
+        "        <> 5-12\n",
+        "          () 6-9\n",
+        "            () 7-8\n",
+        "          [] 10-11\n",
+        "        [] 13- (unclosed)\n",
+        "          [] 14- (unclosed)\n",
+        "            {} 15- (unclosed)\n",
+        "              [] 16-17\n",
+        "              {} 18- (unclosed)\n",
+        "                <> 19-24\n",
+        "                  () 20-21\n",
+        "                  <> 22-23\n",
+    ));
+}
+
+#[test]
+fn corrupted_line_gives_a_partial_tree() {
+    // Everything after the corruption at column 13 is left out
+    let tree = parse_line(&GrouperSet::default(), None, "{([(<{}[<>[]}>{[]{[(<()>").unwrap();
+    assert_eq!(tree.result, LineResult::Corrupted { column: 13, expected: Some(']'), found: '}' });
+    assert_eq!(tree.max_depth(), 7);
+    assert_eq!(tree.to_string(), concat!(
+        "{} 1- (unclosed)\n",
+        "  () 2- (unclosed)\n",
+        "    [] 3- (unclosed)\n",
+        "      () 4- (unclosed)\n",
+        "        <> 5- (unclosed)\n",
+        "          {} 6-7\n",
+        "          [] 8- (unclosed)\n",
+        "            <> 9-10\n",
+        "            [] 11-12\n",
+    ));
+}
@@ -0,0 +1,107 @@
+//! Measures `Cavern::step` throughput on the puzzle input as is, and tiled into
+//! a 1000x1000 grid, against the HashSet-based step it replaced. Run with
+//! `cargo bench --bench step`.
+
+use aoc11::Cavern;
+use std::collections::HashSet;
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+const RUNS: usize = 3;
+
+type Stepper = fn(&mut Cavern) -> usize;
+
+/// The puzzle input repeated `times` times in each direction
+fn tiled_input(times: usize) -> String {
+    let input = include_str!("../aoc11.txt");
+    let mut tiled = String::new();
+    for _ in 0..times {
+        for line in input.lines() {
+            tiled.push_str(&line.repeat(times));
+            tiled.push('\n');
+        }
+    }
+    tiled.pop();
+    tiled
+}
+
+/// The step `Cavern::step` replaced, kept as a baseline. It allocates a set of
+/// flashed cells every step and can push a cell onto the stack many times.
+fn reference_step(cavern: &mut Cavern) -> usize {
+    let mut flash_stack = Vec::new();
+
+    for x in 0..cavern.width() as i64 {
+        for y in 0..cavern.height() as i64 {
+            let energy = cavern.get(x, y).saturating_add(1);
+            cavern.set(x, y, energy);
+            if energy > 9 {
+                flash_stack.push((x, y));
+            }
+        }
+    }
+
+    let mut flashed = HashSet::new();
+    while let Some((x, y)) = flash_stack.pop() {
+        if flashed.contains(&(x, y)) {
+            continue;
+        }
+
+        for dx in -1i64..=1 {
+            for dy in -1i64..=1 {
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+                let energy = cavern.get(x + dx, y + dy).saturating_add(1);
+                cavern.set(x + dx, y + dy, energy);
+                if energy > 9 {
+                    flash_stack.push((x + dx, y + dy));
+                }
+            }
+        }
+
+        flashed.insert((x, y));
+    }
+
+    for &(x, y) in &flashed {
+        cavern.set(x, y, 0);
+    }
+
+    flashed.len()
+}
+
+/// Best of `RUNS` timings of `steps` calls to `step`, each run starting from `cavern`
+fn time_best(cavern: &Cavern, steps: usize, step: Stepper) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let mut cavern = cavern.clone();
+            let start = Instant::now();
+            for _ in 0..steps {
+                step(&mut cavern);
+            }
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let steppers: [(&str, Stepper); 2] = [("bitset", Cavern::step), ("hashset", reference_step)];
+    for (times, steps) in [(1, 100_000), (100, 100)] {
+        let cavern = Cavern::from_reader(Cursor::new(tiled_input(times))).unwrap();
+
+        // Both steps must agree for the comparison to mean anything
+        let (mut fast, mut reference) = (cavern.clone(), cavern.clone());
+        for _ in 0..10 {
+            assert_eq!(fast.step(), reference_step(&mut reference));
+        }
+        assert_eq!(fast.energies(), reference.energies());
+
+        for (name, step) in steppers {
+            let elapsed = time_best(&cavern, steps, step);
+            println!(
+                "{}x{} {}: {} steps in {:?} ({:.0} steps/s)",
+                cavern.width(), cavern.height(), name, steps, elapsed, steps as f64 / elapsed.as_secs_f64()
+            );
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use anyhow::Result;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::Duration;
+use crate::Cavern;
+
+/// Draws the cavern in the terminal after every step. Controls are typed into
+/// the terminal followed by Enter, since stdin holds the puzzle input: an empty
+/// line pauses, and while paused it advances one step, `c` continues and `q` quits.
+pub struct Animation {
+    delay: Duration,
+    paused: bool,
+    /// Lines typed into the terminal, or `None` when there is no terminal
+    commands: Option<Receiver<String>>,
+}
+
+impl Animation {
+    pub fn new(delay: Duration, paused: bool) -> Self {
+        let commands = File::open("/dev/tty").ok().map(|tty| {
+            let (sender, receiver) = mpsc::channel();
+            thread::spawn(move || {
+                for line in BufReader::new(tty).lines() {
+                    match line {
+                        Ok(line) if sender.send(line.trim().to_string()).is_ok() => (),
+                        _ => break,
+                    }
+                }
+            });
+            receiver
+        });
+
+        // Pausing can't be undone without a terminal to type into
+        let paused = paused && commands.is_some();
+        Animation { delay, paused, commands }
+    }
+
+    /// Draws one frame, then waits for the frame delay or, when paused, for a
+    /// command. Returns false if the user quit.
+    pub fn frame(&mut self, cavern: &Cavern, step_num: usize, flashes: usize, total_flashes: usize) -> Result<bool> {
+        let mut stdout = io::stdout().lock();
+        // Clear the screen and move to the top left
+        write!(stdout, "\x1b[H\x1b[2J")?;
+        render(cavern, &mut stdout)?;
+        writeln!(stdout, "step {}: {} flashes, {} in total", step_num, flashes, total_flashes)?;
+        if self.paused {
+            writeln!(stdout, "paused: Enter to step, c to continue, q to quit")?;
+        }
+        stdout.flush()?;
+        drop(stdout);
+
+        if !self.paused {
+            thread::sleep(self.delay);
+        }
+
+        let commands = match &self.commands {
+            Some(commands) => commands,
+            None => return Ok(true),
+        };
+        loop {
+            let command = if self.paused {
+                match commands.recv() {
+                    Ok(command) => command,
+                    // The terminal went away, so carry on without it
+                    Err(_) => "c".to_string(),
+                }
+            } else {
+                match commands.try_recv() {
+                    Ok(command) => command,
+                    Err(TryRecvError::Empty | TryRecvError::Disconnected) => return Ok(true),
+                }
+            };
+
+            match command.as_str() {
+                "q" => return Ok(false),
+                "c" => {
+                    self.paused = false;
+                    return Ok(true);
+                }
+                _ if self.paused => return Ok(true),
+                _ => self.paused = true,
+            }
+        }
+    }
+}
+
+/// Writes the grid with ANSI colors. Cells that flashed on the last step are
+/// highlighted, and the rest get brighter as their energy nears the threshold.
+pub fn render<W: Write>(cavern: &Cavern, out: &mut W) -> io::Result<()> {
+    let mut flashed = vec![false; cavern.len()];
+    for (x, y) in cavern.last_flash_generations().flatten() {
+        flashed[x + y * cavern.width()] = true;
+    }
+    let threshold = cavern.rules().threshold.max(1) as u32;
+
+    for y in 0..cavern.height() {
+        for x in 0..cavern.width() {
+            let energy = cavern.get(x as i64, y as i64);
+            // Energies past 9 are shown as letters, and past 35 as #
+            let digit = char::from_digit(energy as u32, 36).unwrap_or('#');
+            if flashed[x + y * cavern.width()] {
+                // Bold black on bright yellow
+                write!(out, "\x1b[0;1;30;103m{}", digit)?;
+            } else {
+                // Grey levels from the 256 color palette, 232 is black and 255 is white
+                let grey = 236 + (energy as u32 * 19 / threshold).min(19);
+                write!(out, "\x1b[0;38;5;{}m{}", grey, digit)?;
+            }
+        }
+        writeln!(out, "\x1b[0m")?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use crate::Cavern;
+
+/// The eventually periodic sequence of states a cavern steps through. Since
+/// there are finitely many states, every cavern ends up in a cycle, and all the
+/// states up to the end of the first lap of that cycle are distinct.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle {
+    /// Steps before the first state that is part of the cycle
+    pre_period: usize,
+    period: usize,
+    /// Flashes on each step from 1 to `pre_period + period`
+    flashes: Vec<usize>,
+    /// Octopuses in the cavern, which is how many flash on a sync
+    len: usize,
+}
+
+fn state_hash(cavern: &Cavern) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    cavern.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl Cycle {
+    /// Steps a copy of `cavern` until it revisits a state. States are compared
+    /// by hash, and a matching hash is checked against the earlier state, which
+    /// is recreated by stepping from the start again.
+    pub fn find(cavern: &Cavern) -> Self {
+        let mut current = cavern.clone();
+        // Steps after which each state hash was seen
+        let mut seen: HashMap<u64, Vec<usize>> = HashMap::new();
+        let mut flashes = Vec::new();
+
+        loop {
+            let step_num = flashes.len();
+            let hash = state_hash(&current);
+            let earlier_steps = seen.entry(hash).or_default();
+            for &earlier in earlier_steps.iter() {
+                if advanced(cavern, earlier) == current {
+                    return Cycle { pre_period: earlier, period: step_num - earlier, flashes, len: cavern.len() };
+                }
+            }
+            earlier_steps.push(step_num);
+
+            flashes.push(current.step());
+        }
+    }
+
+    /// Steps taken before entering the cycle
+    pub fn pre_period(&self) -> usize {
+        self.pre_period
+    }
+
+    pub fn period(&self) -> usize {
+        self.period
+    }
+
+    /// First step on which every octopus flashes, or `None` if that never happens
+    pub fn first_sync(&self) -> Option<usize> {
+        // An empty cavern doesn't flash, so it doesn't sync either
+        if self.len == 0 {
+            return None;
+        }
+        self.flashes.iter().position(|&flashes| flashes == self.len).map(|idx| idx + 1)
+    }
+
+    /// The step at most `pre_period + period` that ends in the same state as `step_num`
+    pub fn equivalent_step(&self, step_num: usize) -> usize {
+        if step_num <= self.pre_period + self.period {
+            step_num
+        } else {
+            self.pre_period + (step_num - self.pre_period - 1) % self.period + 1
+        }
+    }
+
+    /// Flashes on step `step_num`, counting from 1
+    pub fn flashes_on_step(&self, step_num: usize) -> usize {
+        match step_num {
+            0 => 0,
+            _ => self.flashes[self.equivalent_step(step_num) - 1],
+        }
+    }
+
+    /// Flashes over the first `steps` steps, without simulating laps of the cycle
+    pub fn total_flashes(&self, steps: usize) -> u128 {
+        let sum = |flashes: &[usize]| flashes.iter().map(|&flashes| flashes as u128).sum::<u128>();
+        if steps <= self.flashes.len() {
+            return sum(&self.flashes[..steps]);
+        }
+
+        let lap = &self.flashes[self.pre_period..];
+        let laps = (steps - self.pre_period) / self.period;
+        let rest = (steps - self.pre_period) % self.period;
+        sum(&self.flashes[..self.pre_period]) + laps as u128 * sum(lap) + sum(&lap[..rest])
+    }
+
+    /// The state of `cavern` after `step_num` steps, which must be the cavern
+    /// this cycle was found for. At most `pre_period + period` steps are simulated.
+    pub fn jump(&self, cavern: &Cavern, step_num: usize) -> Cavern {
+        advanced(cavern, self.equivalent_step(step_num))
+    }
+}
+
+/// Copy of `cavern` after `steps` more steps
+fn advanced(cavern: &Cavern, steps: usize) -> Cavern {
+    let mut cavern = cavern.clone();
+    for _ in 0..steps {
+        cavern.step();
+    }
+    cavern
+}
@@ -0,0 +1,46 @@
+use std::ascii;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Why a grid couldn't be read. Lines and columns count from 1.
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    /// A byte that isn't a digit, at a byte offset within its line
+    NotADigit { line: usize, column: usize, byte: u8 },
+    /// A row with a different length from the first one
+    WidthMismatch { line: usize, expected: usize, found: usize },
+    /// Nothing but blank lines, or no lines at all
+    Empty,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Io(err) => write!(f, "reading the grid failed: {}", err),
+            ParseError::NotADigit { line, column, byte } => {
+                write!(f, "line {}, column {}: expected a digit, found '{}'", line, column, ascii::escape_default(*byte))
+            }
+            ParseError::WidthMismatch { line, expected, found } => {
+                write!(f, "line {}: expected {} digits like the first line, found {}", line, expected, found)
+            }
+            ParseError::Empty => write!(f, "the grid has no rows"),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(err: io::Error) -> Self {
+        ParseError::Io(err)
+    }
+}
@@ -0,0 +1,69 @@
+use serde_json::{json, Value};
+use std::io::{self, Write};
+use crate::Cavern;
+
+/// One octopus flashing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Flash {
+    pub step: usize,
+    /// How many flashes in a row led to this one within its step, 0 for
+    /// octopuses that reached the threshold from the step's own increment
+    pub generation: usize,
+    pub x: usize,
+    pub y: usize,
+}
+
+/// The flashes of one step, grouped by cascade generation
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StepFlashes {
+    step: usize,
+    generations: Vec<Vec<(usize, usize)>>,
+}
+
+/// Every flash over a run of steps, for analyzing how the cascades spread
+#[derive(Debug, Clone, Default)]
+pub struct EventLog {
+    steps: Vec<StepFlashes>,
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the flashes of the step that just brought `cavern` to `step_num`
+    pub fn record(&mut self, step_num: usize, cavern: &Cavern) {
+        self.steps.push(StepFlashes { step: step_num, generations: cavern.last_flash_generations().collect() });
+    }
+
+    /// Every recorded flash, by step and then by generation
+    pub fn flashes(&self) -> impl Iterator<Item = Flash> + '_ {
+        self.steps.iter().flat_map(|step| {
+            step.generations.iter().enumerate().flat_map(move |(generation, cells)| {
+                cells.iter().map(move |&(x, y)| Flash { step: step.step, generation, x, y })
+            })
+        })
+    }
+
+    /// Writes one `step,generation,x,y` row per flash, after a header
+    pub fn write_csv<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "step,generation,x,y")?;
+        for flash in self.flashes() {
+            writeln!(out, "{},{},{},{}", flash.step, flash.generation, flash.x, flash.y)?;
+        }
+        Ok(())
+    }
+
+    /// An object per recorded step, including ones without flashes, with the
+    /// `[x, y]` cells of each generation
+    pub fn to_json(&self) -> Value {
+        Value::from(self.steps
+            .iter()
+            .map(|step| json!({
+                "step": step.step,
+                "flashes": step.generations.iter().map(Vec::len).sum::<usize>(),
+                "generations": step.generations,
+            }))
+            .collect::<Vec<_>>())
+    }
+}
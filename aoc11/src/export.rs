@@ -0,0 +1,66 @@
+use anyhow::{anyhow, Result};
+use std::io::Write;
+use crate::Cavern;
+
+/// Largest `scale` the command line accepts for exported images
+pub const MAX_SCALE: usize = 256;
+
+/// Grey level of each cell, row by row, going from black at 0 to white at the
+/// flash threshold
+fn grey_levels(cavern: &Cavern) -> Vec<u8> {
+    let threshold = cavern.rules().threshold.max(1) as u32;
+    cavern.energies().iter().map(|&energy| (energy as u32 * 255 / threshold).min(255) as u8).collect()
+}
+
+/// Width and height in pixels of the image of `cavern` at `scale`, and the
+/// number of pixels, or an error if any of them overflows
+fn scaled_size(cavern: &Cavern, scale: usize) -> Result<(usize, usize, usize)> {
+    let too_large = || anyhow!("a {}x{} grid at scale {} is too large to export", cavern.width(), cavern.height(), scale);
+    let width = cavern.width().checked_mul(scale).ok_or_else(too_large)?;
+    let height = cavern.height().checked_mul(scale).ok_or_else(too_large)?;
+    let pixels = width.checked_mul(height).ok_or_else(too_large)?;
+    Ok((width, height, pixels))
+}
+
+/// Grey levels with each cell blown up to a `scale` by `scale` square, given
+/// the pixel count from `scaled_size`
+fn scaled_rows(cavern: &Cavern, scale: usize, len: usize) -> Vec<u8> {
+    let levels = grey_levels(cavern);
+    let mut pixels = Vec::with_capacity(len);
+    if cavern.width() == 0 {
+        return pixels;
+    }
+    for row in levels.chunks(cavern.width()) {
+        let scaled_row = row.iter().flat_map(|&level| std::iter::repeat_n(level, scale)).collect::<Vec<_>>();
+        for _ in 0..scale {
+            pixels.extend_from_slice(&scaled_row);
+        }
+    }
+    pixels
+}
+
+/// Writes the energy field as a binary PPM, one `scale` pixel square per octopus
+pub fn write_ppm<W: Write>(cavern: &Cavern, scale: usize, mut out: W) -> Result<()> {
+    let (width, height, len) = scaled_size(cavern, scale)?;
+    let bytes = len.checked_mul(3).ok_or_else(|| anyhow!("a {}x{} image is too large to export", width, height))?;
+    write!(out, "P6\n{} {}\n255\n", width, height)?;
+    let mut rgb = Vec::with_capacity(bytes);
+    rgb.extend(scaled_rows(cavern, scale, len).into_iter().flat_map(|level| [level; 3]));
+    out.write_all(&rgb)?;
+    Ok(())
+}
+
+/// Writes the energy field as a greyscale PNG, one `scale` pixel square per octopus
+pub fn write_png<W: Write>(cavern: &Cavern, scale: usize, out: W) -> Result<()> {
+    let (width, height, len) = scaled_size(cavern, scale)?;
+    let width = u32::try_from(width)?;
+    let height = u32::try_from(height)?;
+    let mut encoder = png::Encoder::new(out, width, height);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&scaled_rows(cavern, scale, len))?;
+    writer.finish()?;
+    Ok(())
+}
@@ -0,0 +1,319 @@
+pub mod animate;
+pub mod cycle;
+pub mod error;
+pub mod events;
+pub mod export;
+pub mod rules;
+pub mod snapshot;
+pub mod topology;
+
+use std::hash::{Hash, Hasher};
+use std::io;
+
+pub use cycle::Cycle;
+pub use error::ParseError;
+pub use events::{EventLog, Flash};
+pub use rules::EnergyRules;
+pub use snapshot::Snapshot;
+pub use topology::{Boundary, Neighborhood, Topology};
+
+/// Fixed size set of cell indices, one bit per cell
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    fn new(len: usize) -> Self {
+        BitSet { words: vec![0; len.div_ceil(64)] }
+    }
+
+    /// Adds `idx`, returning false if it was already present
+    fn insert(&mut self, idx: usize) -> bool {
+        let (word, bit) = (idx / 64, 1 << (idx % 64));
+        let absent = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+        absent
+    }
+
+    fn remove(&mut self, idx: usize) {
+        self.words[idx / 64] &= !(1 << (idx % 64));
+    }
+}
+
+/// Grid of octopus energy levels. Coordinates are signed so that neighbors of
+/// the edge cells can be addressed; anything outside the grid reads as 0 and
+/// ignores writes. Which cells a flash reaches is up to the `Topology`, and how
+/// energy behaves is up to the `EnergyRules`.
+#[derive(Debug, Clone)]
+pub struct Cavern {
+    octopuses: Vec<u8>,
+    width: usize,
+    height: usize,
+    topology: Topology,
+    rules: EnergyRules,
+    /// Steps each octopus has left to rest after flashing, empty when the
+    /// rules have no refractory period
+    resting: Vec<u8>,
+    /// Cells that have flashed during the current step, empty between steps
+    flashed: BitSet,
+    /// Cells in the order they flashed during the last step, each pushed once
+    flash_queue: Vec<usize>,
+    /// End of each cascade generation in `flash_queue`
+    generation_ends: Vec<usize>,
+}
+
+/// Caverns are equal when their grids, rules and resting octopuses are,
+/// whatever flashed last
+impl PartialEq for Cavern {
+    fn eq(&self, other: &Self) -> bool {
+        (self.width, self.height, self.topology, self.rules) == (other.width, other.height, other.topology, other.rules)
+            && self.octopuses == other.octopuses
+            && self.resting == other.resting
+    }
+}
+
+impl Eq for Cavern {}
+
+impl Hash for Cavern {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.width, self.height, self.topology, self.rules).hash(state);
+        self.octopuses.hash(state);
+        self.resting.hash(state);
+    }
+}
+
+impl Cavern {
+    /// Reads rows of digits, one row per line. Lines may end in CRLF, and
+    /// blank lines at the end are ignored.
+    pub fn from_reader<R: io::BufRead>(reader: R) -> Result<Self, ParseError> {
+        let mut octopuses = Vec::new();
+        let mut opt_width = None;
+        let mut height = 0;
+        // Blank lines are only allowed at the end, so the first one is kept
+        // until another row shows whether it was
+        let mut first_blank_line = None;
+
+        for (idx, line) in reader.split(b'\n').enumerate() {
+            let mut line = line?;
+            let line_num = idx + 1;
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+
+            if line.is_empty() {
+                first_blank_line.get_or_insert(line_num);
+                continue;
+            }
+            if let Some(blank_line) = first_blank_line {
+                return Err(ParseError::WidthMismatch { line: blank_line, expected: opt_width.unwrap_or(line.len()), found: 0 });
+            }
+
+            let this_width = line.len();
+            if let Some(width) = opt_width {
+                if this_width != width {
+                    return Err(ParseError::WidthMismatch { line: line_num, expected: width, found: this_width });
+                }
+            } else {
+                opt_width = Some(this_width);
+            }
+
+            for (column, b) in line.into_iter().enumerate() {
+                match b {
+                    b'0'..=b'9' => octopuses.push(b - b'0'),
+                    _ => return Err(ParseError::NotADigit { line: line_num, column: column + 1, byte: b }),
+                }
+            }
+
+            height += 1;
+        }
+
+        let width = opt_width.ok_or(ParseError::Empty)?;
+        Ok(Self::new(octopuses, width, height))
+    }
+
+    fn new(octopuses: Vec<u8>, width: usize, height: usize) -> Self {
+        Self {
+            flashed: BitSet::new(octopuses.len()),
+            flash_queue: Vec::new(),
+            generation_ends: Vec::new(),
+            octopuses,
+            width,
+            height,
+            topology: Topology::default(),
+            rules: EnergyRules::default(),
+            resting: Vec::new(),
+        }
+    }
+
+    /// Uses `topology` for flashes instead of the puzzle's Moore neighborhood and
+    /// walls. Fails if the grid's height doesn't suit it.
+    pub fn with_topology(mut self, topology: Topology) -> anyhow::Result<Self> {
+        topology.check_height(self.height)?;
+        self.topology = topology;
+        Ok(self)
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    /// Uses `rules` instead of the puzzle's, with no octopus resting to begin with
+    pub fn with_rules(mut self, rules: EnergyRules) -> Self {
+        self.rules = rules;
+        self.resting = if rules.refractory > 0 { vec![0; self.octopuses.len()] } else { Vec::new() };
+        self
+    }
+
+    pub fn rules(&self) -> EnergyRules {
+        self.rules
+    }
+
+    /// Whether the octopus at `idx` is in its refractory period and gains no energy
+    fn is_resting(&self, idx: usize) -> bool {
+        self.resting.get(idx).is_some_and(|&steps| steps > 0)
+    }
+
+    /// Writes the grid in the format `from_reader` reads, a digit per octopus
+    /// with a newline between rows. Energies above 9 have no digit and fail.
+    pub fn write_digits<W: io::Write>(&self, mut out: W) -> io::Result<()> {
+        for (row_num, row) in self.octopuses.chunks(self.width.max(1)).enumerate() {
+            if row_num > 0 {
+                out.write_all(b"\n")?;
+            }
+            let digits = row
+                .iter()
+                .map(|&energy| match energy {
+                    0..=9 => Ok(b'0' + energy),
+                    _ => Err(io::Error::new(io::ErrorKind::InvalidData, format!("energy {} on row {} is not a digit", energy, row_num + 1))),
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+            out.write_all(&digits)?;
+        }
+        Ok(())
+    }
+
+    /// Grid of `width` by `height` octopuses that all have the same `energy`
+    pub fn filled(width: usize, height: usize, energy: u8) -> Self {
+        Self::new(vec![energy; width * height], width, height)
+    }
+
+    /// Energy of every octopus, row by row
+    pub fn energies(&self) -> &[u8] {
+        &self.octopuses
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Index into `octopuses`, or `None` when out of bounds
+    fn index(&self, x: i64, y: i64) -> Option<usize> {
+        let x = usize::try_from(x).ok()?;
+        let y = usize::try_from(y).ok()?;
+        if x < self.width && y < self.height {
+            Some(x + y * self.width)
+        } else {
+            None
+        }
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> u8 {
+        match self.index(x, y) {
+            Some(idx) => self.octopuses[idx],
+            None => 0,
+        }
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, e: u8) {
+        if let Some(idx) = self.index(x, y) {
+            self.octopuses[idx] = e;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.octopuses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.octopuses.is_empty()
+    }
+
+    /// Cells that flashed on the last step, as (x, y), grouped by cascade
+    /// generation. Generation 0 reached the threshold from the step's own
+    /// increment, and each later one from the flashes of the one before.
+    pub fn last_flash_generations(&self) -> impl Iterator<Item = Vec<(usize, usize)>> + '_ {
+        let starts = std::iter::once(0).chain(self.generation_ends.iter().copied());
+        starts.zip(&self.generation_ends).map(|(start, &end)| {
+            self.flash_queue[start..end].iter().map(|&idx| (idx % self.width, idx / self.width)).collect()
+        })
+    }
+
+    /// Advances one step and returns how many octopuses flashed
+    pub fn step(&mut self) -> usize {
+        self.flash_queue.clear();
+        self.generation_ends.clear();
+
+        let rules = self.rules;
+        for idx in 0..self.octopuses.len() {
+            if self.is_resting(idx) {
+                continue;
+            }
+            let energy = self.octopuses[idx].saturating_add(rules.step_increment);
+            self.octopuses[idx] = energy;
+            if energy > rules.threshold && self.flashed.insert(idx) {
+                self.flash_queue.push(idx);
+            }
+        }
+
+        // Cells are only queued the first time they reach the threshold, so
+        // each one flashes once. The queue is worked through a generation at a
+        // time, where a generation is everything queued by the one before it.
+        let mut next = 0;
+        while next < self.flash_queue.len() {
+            let generation_end = self.flash_queue.len();
+            self.generation_ends.push(generation_end);
+
+            while next < generation_end {
+                let idx = self.flash_queue[next];
+                next += 1;
+
+                let (x, y) = (idx % self.width, idx / self.width);
+                for &(dx, dy) in self.topology.neighborhood.offsets(y) {
+                    let boundary = self.topology.boundary;
+                    let (nx, ny) = match (boundary.resolve(x as i64 + dx, self.width), boundary.resolve(y as i64 + dy, self.height)) {
+                        (Some(nx), Some(ny)) => (nx, ny),
+                        _ => continue,
+                    };
+
+                    let neighbor = nx + ny * self.width;
+                    if self.is_resting(neighbor) {
+                        continue;
+                    }
+                    let energy = self.octopuses[neighbor].saturating_add(rules.flash_increment);
+                    self.octopuses[neighbor] = energy;
+                    if energy > rules.threshold && self.flashed.insert(neighbor) {
+                        self.flash_queue.push(neighbor);
+                    }
+                }
+            }
+        }
+
+        for steps in &mut self.resting {
+            *steps = steps.saturating_sub(1);
+        }
+        for &idx in &self.flash_queue {
+            self.octopuses[idx] = rules.reset;
+            self.flashed.remove(idx);
+            if rules.refractory > 0 {
+                self.resting[idx] = rules.refractory;
+            }
+        }
+
+        self.flash_queue.len()
+    }
+}
@@ -1,144 +1,322 @@
-use std::io;
-use std::collections::HashSet;
-use anyhow::{anyhow, bail, Result};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::str::FromStr;
+use std::time::Duration;
+use anyhow::{anyhow, bail, Context, Error, Result};
+use aoc11::{Boundary, Cavern, Cycle, EnergyRules, EventLog, Neighborhood, Snapshot, Topology};
+use aoc11::animate::Animation;
+use aoc11::export::{write_png, write_ppm, MAX_SCALE};
 
-const PART2: bool = true;
-
-struct Cavern {
-    octopuses: Vec<u8>,
-    width: i8,
-    height: i8,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+    One,
+    Two,
+    Both,
 }
 
-impl Cavern {
-    fn from_reader<R: io::BufRead>(reader: R) -> Result<Self> {
-        let mut octopuses = Vec::new();
-        let mut opt_width = None;
-        let mut height = 0i8;
-
-        for line in reader.split(b'\n') {
-            let line = line?;
-            let this_width = i8::try_from(line.len())?;
-
-            if let Some(width) = opt_width {
-                if this_width != width {
-                    bail!("Expected line width {}, found {} on line {}", width, this_width, height);
-                }
-            } else {
-                opt_width = Some(this_width);
-            }
-
-            const ZERO: u8 = 48;
-            const NINE: u8 = ZERO + 9;
-
-            for b in line.into_iter() {
-                if b >= ZERO && b <= NINE {
-                    octopuses.push(b - ZERO);
-                }  else {
-                    bail!("Input is not a digit");
-                }
-            }
-
-            height += 1;
+impl FromStr for Part {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" => Ok(Part::One),
+            "2" => Ok(Part::Two),
+            "both" => Ok(Part::Both),
+            _ => bail!("unknown part {:?}, expected 1, 2 or both", s),
         }
-
-        let cavern = Self {
-            octopuses,
-            width: opt_width.ok_or_else(|| anyhow!("No lines found"))?,
-            height,
-        };
-
-        Ok(cavern)
     }
+}
 
-    fn get(&self, x: i8, y: i8) -> u8 {
-        if x >= 0 && x < self.width && y >= 0 && y < self.height {
-            let idx = x as usize + y as usize * self.width as usize;
-            self.octopuses[idx]
-        } else {
-            0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EventFormat {
+    /// One row per flash
+    Csv,
+    /// One object per step, with the flashes grouped by generation
+    Json,
+}
+
+impl FromStr for EventFormat {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(EventFormat::Csv),
+            "json" => Ok(EventFormat::Json),
+            _ => bail!("unknown event format {:?}, expected csv or json", s),
         }
     }
+}
+
+/// Grid file formats, picked by the extension of the path written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    /// The puzzle's digit grid
+    Digits,
+    Ppm,
+    Png,
+}
 
-    fn set(&mut self, x: i8, y: i8, e: u8) {
-        if x >= 0 && x < self.width && y >= 0 && y < self.height {
-            let idx = x as usize + y as usize * self.width as usize;
-            self.octopuses[idx] = e;
+impl ExportFormat {
+    fn from_path(path: &str) -> Result<Self> {
+        match path.rsplit_once('.').map(|(_, extension)| extension.to_ascii_lowercase()).as_deref() {
+            Some("txt") => Ok(ExportFormat::Digits),
+            Some("ppm") => Ok(ExportFormat::Ppm),
+            Some("png") => Ok(ExportFormat::Png),
+            _ => bail!("can't tell the format of {:?}, expected a .txt, .ppm or .png extension", path),
         }
     }
+}
 
-    fn len(&self) -> usize {
-        self.octopuses.len()
-    }
+struct Options {
+    part: Part,
+    /// Steps to count flashes over for part 1
+    steps: usize,
+    /// Count part 1 flashes up to the first full sync instead of a fixed step count
+    until_sync: bool,
+    /// Print the cycle the grid ends up in, and use it for part 1 even when part 2 isn't wanted
+    cycle: bool,
+    /// Cells a flash reaches and what happens at the edges
+    topology: Topology,
+    rules: EnergyRules,
+    /// Draw the grid in the terminal after every step
+    animate: bool,
+    /// Time each animation frame is shown for
+    delay: Duration,
+    /// Start the animation paused
+    paused: bool,
+    /// File to write every flash of the simulated steps to
+    events_path: Option<String>,
+    events_format: EventFormat,
+    /// Snapshot to carry on from instead of reading the grid from stdin
+    resume_path: Option<String>,
+    /// File to write a snapshot of the state at step `at` to
+    save_path: Option<String>,
+    /// File to write the grid at step `at` to, as digits or an image
+    export_path: Option<String>,
+    /// Step to save or export, defaulting to the starting step
+    at: Option<usize>,
+    /// Pixels per octopus along each side of exported images
+    scale: usize,
+}
 
-    fn step(&mut self) -> usize {
-        let mut flash_stack = Vec::new();
+impl Options {
+    fn from_args<I: Iterator<Item = String>>(mut args: I) -> Result<Self> {
+        let mut part = Part::Two;
+        let mut steps = 100;
+        let mut until_sync = false;
+        let mut cycle = false;
+        let mut neighborhood = Neighborhood::default();
+        let mut boundary = Boundary::default();
+        let mut rules = EnergyRules::default();
+        let mut animate = false;
+        let mut delay = Duration::from_millis(100);
+        let mut paused = false;
+        let mut events_path = None;
+        let mut events_format = EventFormat::Csv;
+        let mut resume_path = None;
+        let mut save_path = None;
+        let mut export_path = None;
+        let mut at = None;
+        let mut scale = 1;
 
-        for x in 0..self.width {
-            for y in 0..self.height {
-                let energy = self.get(x, y).saturating_add(1);
-                self.set(x, y, energy);
-                if energy > 9 {
-                    flash_stack.push((x, y));
-                }
+        while let Some(arg) = args.next() {
+            if arg == "--part" {
+                part = args.next().ok_or_else(|| anyhow!("--part requires a value"))?.parse()?;
+            } else if let Some(value) = arg.strip_prefix("--part=") {
+                part = value.parse()?;
+            } else if arg == "--steps" {
+                steps = args.next().ok_or_else(|| anyhow!("--steps requires a value"))?.parse()?;
+            } else if let Some(value) = arg.strip_prefix("--steps=") {
+                steps = value.parse()?;
+            } else if arg == "--until-sync" {
+                until_sync = true;
+            } else if arg == "--cycle" {
+                cycle = true;
+            } else if arg == "--neighborhood" {
+                neighborhood = args.next().ok_or_else(|| anyhow!("--neighborhood requires a value"))?.parse()?;
+            } else if arg == "--boundary" {
+                boundary = args.next().ok_or_else(|| anyhow!("--boundary requires a value"))?.parse()?;
+            } else if arg == "--threshold" {
+                rules.threshold = args.next().ok_or_else(|| anyhow!("--threshold requires a value"))?.parse()?;
+            } else if arg == "--step-increment" {
+                rules.step_increment = args.next().ok_or_else(|| anyhow!("--step-increment requires a value"))?.parse()?;
+            } else if arg == "--flash-increment" {
+                rules.flash_increment = args.next().ok_or_else(|| anyhow!("--flash-increment requires a value"))?.parse()?;
+            } else if arg == "--reset" {
+                rules.reset = args.next().ok_or_else(|| anyhow!("--reset requires a value"))?.parse()?;
+            } else if arg == "--refractory" {
+                rules.refractory = args.next().ok_or_else(|| anyhow!("--refractory requires a step count"))?.parse()?;
+            } else if arg == "--animate" {
+                animate = true;
+            } else if arg == "--delay" {
+                let millis = args.next().ok_or_else(|| anyhow!("--delay requires milliseconds"))?.parse()?;
+                delay = Duration::from_millis(millis);
+            } else if arg == "--paused" {
+                paused = true;
+            } else if arg == "--events" {
+                events_path = Some(args.next().ok_or_else(|| anyhow!("--events requires a path"))?);
+            } else if arg == "--events-format" {
+                events_format = args.next().ok_or_else(|| anyhow!("--events-format requires a value"))?.parse()?;
+            } else if arg == "--resume" {
+                resume_path = Some(args.next().ok_or_else(|| anyhow!("--resume requires a path"))?);
+            } else if arg == "--save" {
+                save_path = Some(args.next().ok_or_else(|| anyhow!("--save requires a path"))?);
+            } else if arg == "--export" {
+                export_path = Some(args.next().ok_or_else(|| anyhow!("--export requires a path"))?);
+            } else if arg == "--at" {
+                at = Some(args.next().ok_or_else(|| anyhow!("--at requires a step"))?.parse()?);
+            } else if arg == "--scale" {
+                scale = args.next().ok_or_else(|| anyhow!("--scale requires a value"))?.parse()?;
+            } else {
+                bail!("unknown argument {:?}", arg);
             }
         }
 
-        let mut flashed = HashSet::new();
-        while let Some((x, y)) = flash_stack.pop() {
-            if flashed.contains(&(x, y)) {
-                continue;
-            }
-
-            for dx in -1i8..=1 {
-                for dy in -1i8..=1 {
-                    if dx == 0 && dy == 0 {
-                        continue;
-                    }
-                    let energy = self.get(x + dx, y + dy).saturating_add(1);
-                    self.set(x + dx, y + dy, energy);
-                    if energy > 9 {
-                        flash_stack.push((x + dx, y + dy));
-                    }
-                }
-            }
+        if at.is_some() && save_path.is_none() && export_path.is_none() {
+            bail!("--at only applies to --save and --export");
+        }
+        if let Some(path) = &export_path {
+            ExportFormat::from_path(path)?;
+        }
+        if !(1..=MAX_SCALE).contains(&scale) {
+            bail!("--scale must be between 1 and {}", MAX_SCALE);
+        }
+        if paused && !animate {
+            bail!("--paused only applies to --animate");
+        }
 
-            flashed.insert((x, y));
+        let topology = Topology { neighborhood, boundary };
+        if resume_path.is_some() && (topology != Topology::default() || rules != EnergyRules::default()) {
+            bail!("--resume takes the topology and energy rules from the snapshot");
         }
 
-        for &(x, y) in &flashed {
-            self.set(x, y, 0);
+        Ok(Options { part, steps, until_sync, cycle, topology, rules, animate, delay, paused, events_path, events_format, resume_path, save_path, export_path, at, scale })
+    }
+}
+
+/// Writes a snapshot and grid file of the state at step `at`, if asked to.
+/// `cavern` is the state at `start_step`, after `start_flashes` flashes.
+fn save_state(options: &Options, cavern: &Cavern, cycle: Option<&Cycle>, start_step: usize, start_flashes: u64) -> Result<()> {
+    if options.save_path.is_none() && options.export_path.is_none() {
+        return Ok(());
+    }
+
+    let at = options.at.unwrap_or(start_step);
+    let steps = match at.checked_sub(start_step) {
+        Some(steps) => steps,
+        None => bail!("can't save step {}, the run starts at step {}", at, start_step),
+    };
+    let (state, flashes) = match cycle {
+        Some(cycle) => (cycle.jump(cavern, steps), cycle.total_flashes(steps)),
+        None => {
+            let mut state = cavern.clone();
+            let flashes = (0..steps).map(|_| state.step() as u128).sum();
+            (state, flashes)
         }
+    };
 
-        flashed.len()
+    if let Some(path) = &options.save_path {
+        let total_flashes = u64::try_from(start_flashes as u128 + flashes).context("total flashes overflow a snapshot")?;
+        let snapshot = Snapshot { step: at, total_flashes, cavern: state.clone() };
+        let mut out = BufWriter::new(File::create(path).with_context(|| format!("creating {}", path))?);
+        snapshot.write(&mut out)?;
+        out.flush()?;
     }
+    if let Some(path) = &options.export_path {
+        let mut out = BufWriter::new(File::create(path).with_context(|| format!("creating {}", path))?);
+        match ExportFormat::from_path(path)? {
+            ExportFormat::Digits => state.write_digits(&mut out)?,
+            ExportFormat::Ppm => write_ppm(&state, options.scale, &mut out)?,
+            ExportFormat::Png => write_png(&state, options.scale, &mut out)?,
+        }
+        out.flush()?;
+    }
+
+    Ok(())
 }
 
 fn main() -> Result<()> {
-    let mut cavern = Cavern::from_reader(io::stdin().lock())?;
+    let options = Options::from_args(env::args().skip(1))?;
+    let (mut cavern, start_step, start_flashes) = match &options.resume_path {
+        Some(path) => {
+            let file = File::open(path).with_context(|| format!("opening {}", path))?;
+            let snapshot = Snapshot::read(io::BufReader::new(file)).with_context(|| format!("reading snapshot {}", path))?;
+            (snapshot.cavern, snapshot.step, snapshot.total_flashes)
+        }
+        None => (Cavern::from_reader(io::stdin().lock())?.with_topology(options.topology)?.with_rules(options.rules), 0, 0),
+    };
 
-    if PART2 {
-        let mut step_num = 0;
+    let wants_part1 = options.part != Part::Two;
+    let wants_part2 = options.part != Part::One;
 
-        loop {
-            let flashes = cavern.step();
-            step_num += 1;
+    // The cycle tells when the grid never syncs, and lets part 1 skip over laps
+    // of it instead of simulating every step. Steps are counted from the start
+    // of the run, which is later than step 0 when resuming.
+    let cycle = (wants_part2 || options.until_sync || options.cycle).then(|| Cycle::find(&cavern));
+    let sync_steps = cycle.as_ref().and_then(Cycle::first_sync);
+    if let Some(cycle) = cycle.as_ref().filter(|_| options.cycle) {
+        println!("enters a cycle of period {} after step {}", cycle.period(), start_step + cycle.pre_period());
+    }
+    save_state(&options, &cavern, cycle.as_ref(), start_step, start_flashes)?;
 
-            if flashes == cavern.len() {
-                break;
+    let horizon = match sync_steps {
+        _ if !options.until_sync && wants_part1 && options.steps < start_step => {
+            bail!("--steps {} is before step {}, where the snapshot was taken", options.steps, start_step)
+        }
+        _ if !options.until_sync => options.steps.saturating_sub(start_step),
+        Some(sync_steps) => sync_steps,
+        None => bail!("the grid never syncs, so --until-sync has no horizon"),
+    };
+    let mut part1_flashes = cycle.as_ref().map(|cycle| cycle.total_flashes(horizon));
+
+    let mut events = options.events_path.is_some().then(EventLog::new);
+    if part1_flashes.is_none() || options.animate || events.is_some() {
+        let mut animation = options.animate.then(|| Animation::new(options.delay, options.paused));
+        if let Some(animation) = &mut animation {
+            if !animation.frame(&cavern, start_step, 0, start_flashes as usize)? {
+                return Ok(());
             }
         }
 
-        println!("all flashes on step {}", step_num);
-    } else {
+        // Run until every wanted answer has been shown
+        let part1_steps = if wants_part1 { horizon } else { 0 };
+        let part2_steps = sync_steps.filter(|_| wants_part2).unwrap_or(0);
         let mut total_flashes = 0;
-
-        for _i in 0..100 {
+        for steps in 1..=part1_steps.max(part2_steps) {
             let flashes = cavern.step();
             total_flashes += flashes;
+
+            if let Some(events) = &mut events {
+                events.record(start_step + steps, &cavern);
+            }
+            if let Some(animation) = &mut animation {
+                if !animation.frame(&cavern, start_step + steps, flashes, start_flashes as usize + total_flashes)? {
+                    return Ok(());
+                }
+            }
+            if steps == horizon {
+                part1_flashes = Some(total_flashes as u128);
+            }
         }
+        part1_flashes.get_or_insert(0);
+    }
 
-        println!("num flashes = {}", total_flashes);
+    if let (Some(events), Some(path)) = (&events, &options.events_path) {
+        let mut out = BufWriter::new(File::create(path).with_context(|| format!("creating {}", path))?);
+        match options.events_format {
+            EventFormat::Csv => events.write_csv(&mut out)?,
+            EventFormat::Json => serde_json::to_writer(&mut out, &events.to_json())?,
+        }
+        out.flush()?;
+    }
+
+    if let Some(total_flashes) = part1_flashes.filter(|_| wants_part1) {
+        println!("num flashes = {}", start_flashes as u128 + total_flashes);
+    }
+    if wants_part2 {
+        match sync_steps {
+            Some(steps) => println!("all flashes on step {}", start_step + steps),
+            None => println!("never all flash on the same step"),
+        }
     }
 
     Ok(())
@@ -0,0 +1,23 @@
+/// How energy builds up and is released. Energies are stored as u8 and
+/// saturate at 255, so a threshold of 255 means octopuses never flash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EnergyRules {
+    /// Octopuses flash once their energy goes above this
+    pub threshold: u8,
+    /// Energy every octopus gains at the start of a step
+    pub step_increment: u8,
+    /// Energy an octopus gains from each neighbor that flashes
+    pub flash_increment: u8,
+    /// Energy an octopus is left with after flashing
+    pub reset: u8,
+    /// Steps after flashing during which an octopus gains no energy
+    pub refractory: u8,
+}
+
+/// The puzzle's rules: flash above 9, gain 1 per step and per flashing
+/// neighbor, reset to 0 and recover immediately
+impl Default for EnergyRules {
+    fn default() -> Self {
+        EnergyRules { threshold: 9, step_increment: 1, flash_increment: 1, reset: 0, refractory: 0 }
+    }
+}
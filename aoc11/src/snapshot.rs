@@ -0,0 +1,164 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::{json, Value};
+use std::fmt::{self, Write as _};
+use std::io::{Read, Write};
+use crate::{Cavern, EnergyRules, Topology};
+
+/// Everything needed to carry on a simulation from some step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub step: usize,
+    /// Flashes over all the steps up to `step`
+    pub total_flashes: u64,
+    pub cavern: Cavern,
+}
+
+fn field<'a>(value: &'a Value, name: &str) -> Result<&'a Value> {
+    value.get(name).ok_or_else(|| anyhow!("snapshot has no {:?}", name))
+}
+
+fn u64_field(value: &Value, name: &str) -> Result<u64> {
+    field(value, name)?.as_u64().ok_or_else(|| anyhow!("snapshot {:?} is not an unsigned integer", name))
+}
+
+fn u8_field(value: &Value, name: &str) -> Result<u8> {
+    u8::try_from(u64_field(value, name)?).with_context(|| format!("snapshot {:?} is out of range", name))
+}
+
+fn str_field<'a>(value: &'a Value, name: &str) -> Result<&'a str> {
+    field(value, name)?.as_str().ok_or_else(|| anyhow!("snapshot {:?} is not a string", name))
+}
+
+/// Bytes written as a string of two hex digits each, which keeps a grid of
+/// octopuses to one JSON string instead of a JSON number per octopus
+struct Hex<'a>(&'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Format in chunks, as the grid can be large
+        let mut digits = String::with_capacity(8192);
+        for chunk in self.0.chunks(4096) {
+            digits.clear();
+            for byte in chunk {
+                write!(digits, "{:02x}", byte)?;
+            }
+            f.write_str(&digits)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Hex<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Field of bytes written by `Hex`, where a missing field is empty
+fn hex_field(value: &Value, name: &str) -> Result<Vec<u8>> {
+    let digits = match value.get(name) {
+        Some(Value::String(digits)) => digits,
+        Some(_) => bail!("snapshot {:?} is not a string", name),
+        None => return Ok(Vec::new()),
+    };
+    let digit = |byte: u8| char::from(byte).to_digit(16).map(|digit| digit as u8);
+    digits
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| match pair {
+            [high, low] => Some(digit(*high)? << 4 | digit(*low)?),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| anyhow!("snapshot {:?} is not pairs of hex digits", name))
+}
+
+impl Snapshot {
+    /// Every field but the grids, which are small
+    fn header_json(&self) -> Value {
+        let cavern = &self.cavern;
+        let topology = cavern.topology();
+        let rules = cavern.rules();
+        json!({
+            "step": self.step,
+            "total_flashes": self.total_flashes,
+            "width": cavern.width(),
+            "height": cavern.height(),
+            "neighborhood": topology.neighborhood.to_string(),
+            "boundary": topology.boundary.to_string(),
+            "rules": {
+                "threshold": rules.threshold,
+                "step_increment": rules.step_increment,
+                "flash_increment": rules.flash_increment,
+                "reset": rules.reset,
+                "refractory": rules.refractory,
+            },
+        })
+    }
+
+    /// The snapshot as a JSON object. Energies and resting counts are strings
+    /// of two hex digits per octopus, row by row.
+    pub fn to_json(&self) -> Value {
+        let mut value = self.header_json();
+        value["energies"] = Value::from(Hex(self.cavern.energies()).to_string());
+        if !self.cavern.resting.is_empty() {
+            value["resting"] = Value::from(Hex(&self.cavern.resting).to_string());
+        }
+        value
+    }
+
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let width = usize::try_from(u64_field(value, "width")?)?;
+        let height = usize::try_from(u64_field(value, "height")?)?;
+        let topology = Topology {
+            neighborhood: str_field(value, "neighborhood")?.parse()?,
+            boundary: str_field(value, "boundary")?.parse()?,
+        };
+        let rules_value = field(value, "rules")?;
+        let rules = EnergyRules {
+            threshold: u8_field(rules_value, "threshold")?,
+            step_increment: u8_field(rules_value, "step_increment")?,
+            flash_increment: u8_field(rules_value, "flash_increment")?,
+            reset: u8_field(rules_value, "reset")?,
+            refractory: u8_field(rules_value, "refractory")?,
+        };
+
+        let energies = hex_field(value, "energies")?;
+        if Some(energies.len()) != width.checked_mul(height) {
+            bail!("snapshot has {} energies for a {}x{} grid", energies.len(), width, height);
+        }
+        let mut cavern = Cavern::new(energies, width, height).with_topology(topology)?.with_rules(rules);
+
+        let resting = hex_field(value, "resting")?;
+        if !resting.is_empty() {
+            if resting.len() != cavern.resting.len() {
+                bail!("snapshot has {} resting counts for {} octopuses with a refractory period of {}", resting.len(), cavern.len(), rules.refractory);
+            }
+            cavern.resting = resting;
+        }
+
+        Ok(Snapshot { step: usize::try_from(u64_field(value, "step")?)?, total_flashes: u64_field(value, "total_flashes")?, cavern })
+    }
+
+    /// Writes the same JSON as `to_json`, streaming the grids rather than
+    /// building them as strings first
+    pub fn write<W: Write>(&self, out: W) -> Result<()> {
+        let mut serializer = serde_json::Serializer::new(out);
+        let mut map = serializer.serialize_map(None)?;
+        let header = self.header_json();
+        for (name, value) in header.as_object().expect("the header is an object") {
+            map.serialize_entry(&name, &value)?;
+        }
+        map.serialize_entry("energies", &Hex(self.cavern.energies()))?;
+        if !self.cavern.resting.is_empty() {
+            map.serialize_entry("resting", &Hex(&self.cavern.resting))?;
+        }
+        map.end()?;
+        Ok(())
+    }
+
+    pub fn read<R: Read>(reader: R) -> Result<Self> {
+        Self::from_json(&serde_json::from_reader(reader)?)
+    }
+}
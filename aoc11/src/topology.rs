@@ -0,0 +1,127 @@
+use anyhow::{bail, Error, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// Which cells a flash increments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Neighborhood {
+    /// The 4 orthogonal neighbors
+    VonNeumann,
+    /// The 8 orthogonal and diagonal neighbors, as in the puzzle
+    #[default]
+    Moore,
+    /// 6 neighbors, with odd rows shifted half a cell to the right
+    Hex,
+}
+
+const VON_NEUMANN: [(i64, i64); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+const MOORE: [(i64, i64); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+const HEX_EVEN_ROW: [(i64, i64); 6] = [(-1, -1), (0, -1), (-1, 0), (1, 0), (-1, 1), (0, 1)];
+const HEX_ODD_ROW: [(i64, i64); 6] = [(0, -1), (1, -1), (-1, 0), (1, 0), (0, 1), (1, 1)];
+
+impl Neighborhood {
+    /// Offsets of the neighbors of a cell in row `y`
+    pub fn offsets(self, y: usize) -> &'static [(i64, i64)] {
+        match self {
+            Neighborhood::VonNeumann => &VON_NEUMANN,
+            Neighborhood::Moore => &MOORE,
+            Neighborhood::Hex if y.is_multiple_of(2) => &HEX_EVEN_ROW,
+            Neighborhood::Hex => &HEX_ODD_ROW,
+        }
+    }
+}
+
+impl FromStr for Neighborhood {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "von-neumann" | "4" => Ok(Neighborhood::VonNeumann),
+            "moore" | "8" => Ok(Neighborhood::Moore),
+            "hex" | "6" => Ok(Neighborhood::Hex),
+            _ => bail!("unknown neighborhood {:?}, expected von-neumann, moore or hex", s),
+        }
+    }
+}
+
+impl fmt::Display for Neighborhood {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Neighborhood::VonNeumann => write!(f, "von-neumann"),
+            Neighborhood::Moore => write!(f, "moore"),
+            Neighborhood::Hex => write!(f, "hex"),
+        }
+    }
+}
+
+/// What happens to neighbors that fall outside the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Boundary {
+    /// Outside cells don't exist, so energy flashed at them is lost, as in the puzzle
+    #[default]
+    Wall,
+    /// The grid wraps around at every edge. With a hex neighborhood the height
+    /// must be even so that rows keep alternating across the wrap.
+    Toroidal,
+    /// Energy flashed past an edge bounces back onto the cell mirrored across
+    /// the edge row or column, so cells next to the edge can take more than
+    /// one increment from the same flash
+    Reflecting,
+}
+
+impl Boundary {
+    /// Maps a coordinate on an axis of `len` cells into the grid, or `None` if
+    /// it falls off a wall. Coordinates are at most one cell outside the grid.
+    pub fn resolve(self, coord: i64, len: usize) -> Option<usize> {
+        let len = len as i64;
+        let coord = match self {
+            _ if (0..len).contains(&coord) => coord,
+            Boundary::Wall => return None,
+            Boundary::Toroidal => coord.rem_euclid(len),
+            // A single row or column mirrors onto itself
+            Boundary::Reflecting if coord < 0 => (-coord).min(len - 1),
+            Boundary::Reflecting => (2 * (len - 1) - coord).max(0),
+        };
+        Some(coord as usize)
+    }
+}
+
+impl FromStr for Boundary {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wall" => Ok(Boundary::Wall),
+            "toroidal" | "torus" => Ok(Boundary::Toroidal),
+            "reflecting" | "reflect" => Ok(Boundary::Reflecting),
+            _ => bail!("unknown boundary {:?}, expected wall, toroidal or reflecting", s),
+        }
+    }
+}
+
+impl fmt::Display for Boundary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Boundary::Wall => write!(f, "wall"),
+            Boundary::Toroidal => write!(f, "toroidal"),
+            Boundary::Reflecting => write!(f, "reflecting"),
+        }
+    }
+}
+
+/// How cells of a `Cavern` are connected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Topology {
+    pub neighborhood: Neighborhood,
+    pub boundary: Boundary,
+}
+
+impl Topology {
+    /// Checks that the topology works on a grid of `height` rows. Wrapping an odd
+    /// number of hex rows would put two rows with the same shift next to each
+    /// other, so neighbors across the wrap wouldn't be mutual.
+    pub fn check_height(self, height: usize) -> Result<()> {
+        if self.neighborhood == Neighborhood::Hex && self.boundary == Boundary::Toroidal && !height.is_multiple_of(2) {
+            bail!("a toroidal hex grid needs an even height, found {} rows", height);
+        }
+        Ok(())
+    }
+}
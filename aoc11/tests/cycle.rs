@@ -0,0 +1,55 @@
+use aoc11::{Boundary, Cavern, Cycle, Neighborhood, Topology};
+use std::fs::File;
+use std::io::{BufReader, Cursor};
+
+fn sample() -> Cavern {
+    let file = File::open(format!("{}/aoc11-sample.txt", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    Cavern::from_reader(BufReader::new(file)).unwrap()
+}
+
+/// Total flashes and final state after stepping a copy of `cavern` one step at a time
+fn simulate(cavern: &Cavern, steps: usize) -> (u128, Cavern) {
+    let mut cavern = cavern.clone();
+    let total = (0..steps).map(|_| cavern.step() as u128).sum();
+    (total, cavern)
+}
+
+#[test]
+fn sample_syncs_then_repeats_every_ten_steps() {
+    let cycle = Cycle::find(&sample());
+    assert_eq!(cycle.first_sync(), Some(195));
+    // After the sync every octopus is at 0 and they all flash every tenth step
+    assert_eq!((cycle.pre_period(), cycle.period()), (195, 10));
+    assert_eq!(cycle.total_flashes(10), 204);
+    assert_eq!(cycle.total_flashes(100), 1656);
+}
+
+#[test]
+fn total_flashes_and_jump_match_simulation_past_the_first_lap() {
+    let cavern = sample().with_topology(Topology { neighborhood: Neighborhood::Hex, boundary: Boundary::Toroidal }).unwrap();
+    let cycle = Cycle::find(&cavern);
+    let first_lap = cycle.pre_period() + cycle.period();
+
+    for steps in [0, 1, cycle.pre_period(), first_lap, first_lap + 1, 3 * first_lap + 7] {
+        let (total, state) = simulate(&cavern, steps);
+        assert_eq!(cycle.total_flashes(steps), total, "after {} steps", steps);
+        assert_eq!(cycle.jump(&cavern, steps), state, "after {} steps", steps);
+    }
+}
+
+#[test]
+fn never_syncs_is_detected() {
+    let cavern = sample().with_topology(Topology { neighborhood: Neighborhood::VonNeumann, boundary: Boundary::Wall }).unwrap();
+    let cycle = Cycle::find(&cavern);
+    assert_eq!(cycle.first_sync(), None);
+    assert!((1..=cycle.pre_period() + cycle.period()).all(|step_num| cycle.flashes_on_step(step_num) < cavern.len()));
+}
+
+#[test]
+fn lone_octopus_syncs_every_ten_steps() {
+    let cavern = Cavern::from_reader(Cursor::new("7")).unwrap();
+    let cycle = Cycle::find(&cavern);
+    assert_eq!(cycle.first_sync(), Some(3));
+    assert_eq!((cycle.pre_period(), cycle.period()), (0, 10));
+    assert_eq!(cycle.total_flashes(1_000_000_000_000), 100_000_000_000);
+}
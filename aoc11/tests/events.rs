@@ -0,0 +1,42 @@
+use aoc11::{Cavern, EventLog, Flash};
+use std::fs::File;
+use std::io::{BufReader, Cursor};
+
+#[test]
+fn cascade_generations() {
+    // Both 9s flash on their own, and together push the 8 past the threshold
+    let mut cavern = Cavern::from_reader(Cursor::new("989\n000")).unwrap();
+    let mut events = EventLog::new();
+    assert_eq!(cavern.step(), 3);
+    events.record(1, &cavern);
+    assert_eq!(cavern.step(), 0);
+    events.record(2, &cavern);
+
+    assert_eq!(cavern.last_flash_generations().count(), 0);
+    assert_eq!(events.flashes().collect::<Vec<_>>(), [
+        Flash { step: 1, generation: 0, x: 0, y: 0 },
+        Flash { step: 1, generation: 0, x: 2, y: 0 },
+        Flash { step: 1, generation: 1, x: 1, y: 0 },
+    ]);
+
+    let mut csv = Vec::new();
+    events.write_csv(&mut csv).unwrap();
+    assert_eq!(String::from_utf8(csv).unwrap(), "step,generation,x,y\n1,0,0,0\n1,0,2,0\n1,1,1,0\n");
+
+    let json = events.to_json();
+    assert_eq!(json[0]["generations"], serde_json::json!([[[0, 0], [2, 0]], [[1, 0]]]));
+    assert_eq!(json[1], serde_json::json!({ "step": 2, "flashes": 0, "generations": [] }));
+}
+
+#[test]
+fn sample_log_matches_flash_counts() {
+    let file = File::open(format!("{}/aoc11-sample.txt", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let mut cavern = Cavern::from_reader(BufReader::new(file)).unwrap();
+    let mut events = EventLog::new();
+    for step_num in 1..=100 {
+        let flashes = cavern.step();
+        events.record(step_num, &cavern);
+        assert_eq!(events.flashes().filter(|flash| flash.step == step_num).count(), flashes);
+    }
+    assert_eq!(events.flashes().count(), 1656);
+}
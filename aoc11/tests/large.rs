@@ -0,0 +1,61 @@
+use aoc11::Cavern;
+use std::io::Cursor;
+
+const SIZE: usize = 10_000;
+
+/// Digit grid text with `width` columns and `height` rows of `digit`
+fn grid_text(width: usize, height: usize, digit: u8) -> Vec<u8> {
+    let mut text = Vec::with_capacity((width + 1) * height);
+    for row in 0..height {
+        if row > 0 {
+            text.push(b'\n');
+        }
+        text.extend(std::iter::repeat_n(digit, width));
+    }
+    text
+}
+
+#[test]
+fn reads_grids_past_the_old_i8_limit() {
+    let cavern = Cavern::from_reader(Cursor::new(grid_text(300, 200, b'5'))).unwrap();
+    assert_eq!((cavern.width(), cavern.height(), cavern.len()), (300, 200, 60_000));
+    assert_eq!(cavern.get(299, 199), 5);
+}
+
+#[test]
+fn reads_a_10k_grid() {
+    let cavern = Cavern::from_reader(Cursor::new(grid_text(SIZE, SIZE, b'3'))).unwrap();
+    assert_eq!((cavern.width(), cavern.height()), (SIZE, SIZE));
+    assert_eq!(cavern.get(0, 0), 3);
+    assert_eq!(cavern.get(SIZE as i64 - 1, SIZE as i64 - 1), 3);
+}
+
+#[test]
+fn out_of_bounds_reads_zero_and_ignores_writes() {
+    let mut cavern = Cavern::filled(SIZE, SIZE, 4);
+    let last = SIZE as i64 - 1;
+    for (x, y) in [(-1, 0), (0, -1), (SIZE as i64, 0), (0, SIZE as i64), (i64::MIN, i64::MAX), (last + 1, last)] {
+        cavern.set(x, y, 9);
+        assert_eq!(cavern.get(x, y), 0, "({}, {})", x, y);
+    }
+    assert!((0..SIZE as i64).all(|i| cavern.get(i, 0) == 4 && cavern.get(i, last) == 4));
+
+    cavern.set(last, last, 7);
+    assert_eq!(cavern.get(last, last), 7);
+}
+
+#[test]
+fn steps_a_10k_grid() {
+    let mut cavern = Cavern::filled(SIZE, SIZE, 0);
+    let last = SIZE as i64 - 1;
+    // A flash in the far corner only reaches the three neighbors inside the grid
+    cavern.set(last, last, 9);
+    cavern.set(SIZE as i64 / 2, SIZE as i64 / 2, 9);
+
+    assert_eq!(cavern.step(), 2);
+    assert_eq!(cavern.get(last, last), 0);
+    assert_eq!(cavern.get(last - 1, last - 1), 2);
+    assert_eq!(cavern.get(last - 1, last), 2);
+    assert_eq!(cavern.get(SIZE as i64 / 2 + 1, SIZE as i64 / 2), 2);
+    assert_eq!(cavern.get(0, 0), 1);
+}
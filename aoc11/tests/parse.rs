@@ -0,0 +1,53 @@
+use aoc11::{Cavern, ParseError};
+use std::io::Cursor;
+
+fn parse(text: &[u8]) -> Result<Cavern, ParseError> {
+    Cavern::from_reader(Cursor::new(text))
+}
+
+#[test]
+fn crlf_and_trailing_newlines_are_accepted() {
+    let expected = parse(b"123\n456").unwrap();
+    for text in [&b"123\n456\n"[..], b"123\r\n456\r\n", b"123\r\n456", b"123\n456\n\n\r\n"] {
+        assert_eq!(parse(text).unwrap(), expected, "{:?}", String::from_utf8_lossy(text));
+    }
+    assert_eq!((expected.width(), expected.height()), (3, 2));
+}
+
+#[test]
+fn bad_bytes_are_located() {
+    match parse(b"123\n4x6\n") {
+        Err(ParseError::NotADigit { line: 2, column: 2, byte: b'x' }) => (),
+        other => panic!("unexpected {:?}", other),
+    }
+    // A carriage return anywhere but the end of a line is not a digit
+    match parse(b"12\r3\n") {
+        Err(ParseError::NotADigit { line: 1, column: 3, byte: b'\r' }) => (),
+        other => panic!("unexpected {:?}", other),
+    }
+    let err = parse(b"12\xff").unwrap_err();
+    assert_eq!(err.to_string(), "line 1, column 3: expected a digit, found '\\xff'");
+}
+
+#[test]
+fn ragged_rows_and_inner_blank_lines_are_width_mismatches() {
+    match parse(b"123\n45\n") {
+        Err(ParseError::WidthMismatch { line: 2, expected: 3, found: 2 }) => (),
+        other => panic!("unexpected {:?}", other),
+    }
+    match parse(b"123\n\n456\n") {
+        Err(ParseError::WidthMismatch { line: 2, expected: 3, found: 0 }) => (),
+        other => panic!("unexpected {:?}", other),
+    }
+    match parse(b"\n456\n") {
+        Err(ParseError::WidthMismatch { line: 1, expected: 3, found: 0 }) => (),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn blank_input_is_empty() {
+    for text in [&b""[..], b"\n", b"\r\n\n"] {
+        assert!(matches!(parse(text), Err(ParseError::Empty)), "{:?}", text);
+    }
+}
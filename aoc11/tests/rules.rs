@@ -0,0 +1,70 @@
+use aoc11::{Cavern, Cycle, EnergyRules};
+use std::io::Cursor;
+
+fn cavern(text: &str, rules: EnergyRules) -> Cavern {
+    Cavern::from_reader(Cursor::new(text)).unwrap().with_rules(rules)
+}
+
+/// The puzzle's rules spelled out, rather than taken from `EnergyRules::default`
+const PUZZLE_RULES: EnergyRules = EnergyRules { threshold: 9, step_increment: 1, flash_increment: 1, reset: 0, refractory: 0 };
+
+#[test]
+fn default_rules_are_the_puzzle_rules() {
+    assert_eq!(EnergyRules::default(), PUZZLE_RULES);
+
+    // The explicit rules give the sample's known answers
+    let sample = std::fs::read_to_string(format!("{}/aoc11-sample.txt", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let mut cavern = cavern(&sample, PUZZLE_RULES);
+    let flashes = (0..100).map(|_| cavern.step()).sum::<usize>();
+    assert_eq!(flashes, 1656);
+    let first_sync = (101..).find(|_| cavern.step() == cavern.len());
+    assert_eq!(first_sync, Some(195));
+}
+
+#[test]
+fn threshold_increments_and_reset() {
+    let rules = EnergyRules { threshold: 5, step_increment: 2, flash_increment: 3, reset: 1, refractory: 0 };
+    let mut cavern = cavern("40", rules);
+    // 4 + 2 goes past 5 and flashes, giving its neighbor 2 + 3
+    assert_eq!(cavern.step(), 1);
+    assert_eq!(cavern.energies(), [1, 5]);
+    // 5 + 2 flashes, which pushes 1 + 2 + 3 past 5 as well
+    assert_eq!(cavern.step(), 2);
+    assert_eq!(cavern.energies(), [1, 1]);
+}
+
+#[test]
+fn refractory_octopuses_gain_no_energy() {
+    let rules = EnergyRules { refractory: 2, ..EnergyRules::default() };
+    let mut cavern = cavern("99", rules);
+    assert_eq!(cavern.step(), 2);
+    // Both rest for two steps, ignoring the step increment
+    assert_eq!(cavern.step(), 0);
+    assert_eq!(cavern.step(), 0);
+    assert_eq!(cavern.energies(), [0, 0]);
+    assert_eq!(cavern.step(), 0);
+    assert_eq!(cavern.energies(), [1, 1]);
+
+    // A resting octopus isn't pushed over by a flashing neighbor either
+    let mut cavern = cavern_with_one_resting();
+    assert_eq!(cavern.step(), 1);
+    assert_eq!(cavern.energies(), [0, 0]);
+}
+
+/// "90" where the 0 is resting after flashing on the step before
+fn cavern_with_one_resting() -> Cavern {
+    let rules = EnergyRules { refractory: 1, ..EnergyRules::default() };
+    let mut cavern = cavern("79", rules);
+    cavern.step();
+    assert_eq!(cavern.energies(), [9, 0]);
+    cavern
+}
+
+#[test]
+fn resting_octopuses_are_part_of_the_state() {
+    // Without counting the rest, step 1 would look like a repeat of the start
+    let rules = EnergyRules { refractory: 1, ..EnergyRules::default() };
+    let cycle = Cycle::find(&cavern("9", rules));
+    assert_eq!((cycle.pre_period(), cycle.period()), (0, 11));
+    assert_eq!(cycle.first_sync(), Some(1));
+}
@@ -0,0 +1,102 @@
+use aoc11::export::{write_png, write_ppm};
+use aoc11::{Boundary, Cavern, EnergyRules, Neighborhood, Snapshot, Topology};
+use std::fs::File;
+use std::io::{BufReader, Cursor};
+
+fn sample() -> Cavern {
+    let file = File::open(format!("{}/aoc11-sample.txt", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    Cavern::from_reader(BufReader::new(file)).unwrap()
+}
+
+#[test]
+fn digits_round_trip() {
+    let text = std::fs::read_to_string(format!("{}/aoc11-sample.txt", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let mut written = Vec::new();
+    sample().write_digits(&mut written).unwrap();
+    assert_eq!(String::from_utf8(written).unwrap(), text);
+}
+
+#[test]
+fn energies_past_nine_have_no_digit() {
+    let mut cavern = Cavern::filled(2, 2, 0).with_rules(EnergyRules { threshold: 20, ..EnergyRules::default() });
+    cavern.set(1, 1, 12);
+    assert!(cavern.write_digits(Vec::new()).is_err());
+}
+
+#[test]
+fn resuming_a_snapshot_matches_an_uninterrupted_run() {
+    let topology = Topology { neighborhood: Neighborhood::Hex, boundary: Boundary::Reflecting };
+    let rules = EnergyRules { refractory: 2, ..EnergyRules::default() };
+    let mut cavern = sample().with_topology(topology).unwrap().with_rules(rules);
+    let total_flashes = (0..30).map(|_| cavern.step() as u64).sum();
+
+    let snapshot = Snapshot { step: 30, total_flashes, cavern: cavern.clone() };
+    let mut saved = Vec::new();
+    snapshot.write(&mut saved).unwrap();
+    let mut resumed = Snapshot::read(Cursor::new(saved)).unwrap();
+    assert_eq!(resumed, snapshot);
+
+    for _ in 0..30 {
+        assert_eq!(resumed.cavern.step(), cavern.step());
+        assert_eq!(resumed.cavern, cavern);
+    }
+}
+
+#[test]
+fn inconsistent_snapshots_are_rejected() {
+    let mut value = Snapshot { step: 0, total_flashes: 0, cavern: sample() }.to_json();
+    value["width"] = 9.into();
+    assert!(Snapshot::from_json(&value).is_err());
+
+    let mut value = Snapshot { step: 0, total_flashes: 0, cavern: sample() }.to_json();
+    value["rules"]["threshold"] = 256.into();
+    assert!(Snapshot::from_json(&value).is_err());
+
+    let mut value = Snapshot { step: 0, total_flashes: 0, cavern: sample() }.to_json();
+    value["energies"] = "0g".repeat(50).into();
+    assert!(Snapshot::from_json(&value).is_err());
+}
+
+#[test]
+fn grids_are_saved_as_hex_strings() {
+    let mut cavern = Cavern::from_reader(Cursor::new("09\n90")).unwrap().with_rules(EnergyRules { refractory: 1, ..EnergyRules::default() });
+    cavern.set(1, 1, 10);
+    cavern.step();
+    let snapshot = Snapshot { step: 1, total_flashes: 3, cavern };
+
+    let value = snapshot.to_json();
+    // The three flashes each add one to the top left
+    assert_eq!(value["energies"], "04000000");
+    assert_eq!(value["resting"], "00010101");
+    // Streaming the grids gives the same JSON as building them
+    let mut saved = Vec::new();
+    snapshot.write(&mut saved).unwrap();
+    assert_eq!(serde_json::from_slice::<serde_json::Value>(&saved).unwrap(), value);
+}
+
+#[test]
+fn images_scale_each_octopus_to_a_square() {
+    let cavern = Cavern::from_reader(Cursor::new("09\n90")).unwrap();
+
+    let mut ppm = Vec::new();
+    write_ppm(&cavern, 2, &mut ppm).unwrap();
+    let header = b"P6\n4 4\n255\n";
+    assert_eq!(&ppm[..header.len()], header);
+    let pixels = &ppm[header.len()..];
+    assert_eq!(pixels.len(), 4 * 4 * 3);
+    // Top left is black and top right is white at the threshold
+    assert_eq!(&pixels[..12], [0, 0, 0, 0, 0, 0, 255, 255, 255, 255, 255, 255]);
+
+    let mut png = Vec::new();
+    write_png(&cavern, 3, &mut png).unwrap();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+}
+
+#[test]
+fn images_too_large_to_size_are_an_error() {
+    let cavern = Cavern::from_reader(Cursor::new("09\n90")).unwrap();
+    assert!(write_ppm(&cavern, usize::MAX, Vec::new()).is_err());
+    assert!(write_png(&cavern, usize::MAX, Vec::new()).is_err());
+    // The pixel count overflows even though each side fits
+    assert!(write_ppm(&cavern, 1 << (usize::BITS / 2), Vec::new()).is_err());
+}
@@ -0,0 +1,67 @@
+use aoc11::{Boundary, Cavern, Neighborhood, Topology};
+
+/// 5x5 grid of zeroes with a single 9 at (`x`, `y`), stepped once
+fn flash_once(x: i64, y: i64, neighborhood: Neighborhood, boundary: Boundary) -> Cavern {
+    let mut cavern = Cavern::filled(5, 5, 0).with_topology(Topology { neighborhood, boundary }).unwrap();
+    cavern.set(x, y, 9);
+    assert_eq!(cavern.step(), 1);
+    cavern
+}
+
+/// Energy rows after a step, minus the +1 every cell gets
+fn increments(cavern: &Cavern) -> Vec<String> {
+    (0..cavern.height() as i64)
+        .map(|y| (0..cavern.width() as i64).map(|x| char::from(b'0' + cavern.get(x, y).saturating_sub(1))).collect())
+        .collect()
+}
+
+#[test]
+fn neighborhoods_in_the_middle() {
+    // The flashed cell itself reads as 0 - 1, saturated to 0
+    let von_neumann = flash_once(2, 2, Neighborhood::VonNeumann, Boundary::Wall);
+    assert_eq!(increments(&von_neumann), ["00000", "00100", "01010", "00100", "00000"]);
+
+    let moore = flash_once(2, 2, Neighborhood::Moore, Boundary::Wall);
+    assert_eq!(increments(&moore), ["00000", "01110", "01010", "01110", "00000"]);
+
+    // Even rows are shifted left relative to the odd rows around them
+    let hex_even = flash_once(2, 2, Neighborhood::Hex, Boundary::Wall);
+    assert_eq!(increments(&hex_even), ["00000", "01100", "01010", "01100", "00000"]);
+
+    let hex_odd = flash_once(2, 1, Neighborhood::Hex, Boundary::Wall);
+    assert_eq!(increments(&hex_odd), ["00110", "01010", "00110", "00000", "00000"]);
+}
+
+#[test]
+fn boundaries_at_a_corner() {
+    let wall = flash_once(0, 0, Neighborhood::Moore, Boundary::Wall);
+    assert_eq!(increments(&wall), ["01000", "11000", "00000", "00000", "00000"]);
+
+    let toroidal = flash_once(0, 0, Neighborhood::Moore, Boundary::Toroidal);
+    assert_eq!(increments(&toroidal), ["01001", "11001", "00000", "00000", "11001"]);
+
+    // Each of the five offsets off the grid bounces back onto a neighbor
+    let reflecting = flash_once(0, 0, Neighborhood::Moore, Boundary::Reflecting);
+    assert_eq!(increments(&reflecting), ["02000", "24000", "00000", "00000", "00000"]);
+}
+
+#[test]
+fn toroidal_cascade_wraps_around() {
+    // A full row of 9s on a torus lights up the rows on either side, including
+    // across the top and bottom edges
+    let mut cavern = Cavern::filled(4, 3, 0).with_topology(Topology { neighborhood: Neighborhood::VonNeumann, boundary: Boundary::Toroidal }).unwrap();
+    for x in 0..4 {
+        cavern.set(x, 0, 9);
+    }
+    assert_eq!(cavern.step(), 4);
+    assert_eq!(increments(&cavern), ["0000", "1111", "1111"]);
+}
+
+#[test]
+fn toroidal_hex_needs_an_even_height() {
+    let topology = Topology { neighborhood: Neighborhood::Hex, boundary: Boundary::Toroidal };
+    assert!(Cavern::filled(4, 3, 0).with_topology(topology).is_err());
+    assert!(Cavern::filled(3, 4, 0).with_topology(topology).is_ok());
+    // Walls don't wrap, so any height works
+    assert!(Cavern::filled(4, 3, 0).with_topology(Topology { neighborhood: Neighborhood::Hex, boundary: Boundary::Wall }).is_ok());
+}